@@ -318,6 +318,10 @@ impl<V: VertexDef + Sized + 'static> VertexBuffer<V> {
     pub fn buffer(&self) -> &Buffer {
         &self.buffer
     }
+
+    pub fn delete(&mut self) {
+        self.buffer.delete()
+    }
 }
 
 impl<V: VertexDef + Sized + 'static> AsRef<Buffer> for VertexBuffer<V> {
@@ -51,6 +51,8 @@ barrier!(ShaderStorageBarrier, gl::SHADER_STORAGE_BARRIER_BIT);
 barrier!(ShaderImageAccessBarrier, gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
 barrier!(CommandBarrier, gl::COMMAND_BARRIER_BIT);
 barrier!(BufferUpdateBarrier, gl::BUFFER_UPDATE_BARRIER_BIT);
+barrier!(TextureUpdateBarrier, gl::TEXTURE_UPDATE_BARRIER_BIT);
+barrier!(TextureFetchBarrier, gl::TEXTURE_FETCH_BARRIER_BIT);
 barrier!(AllBarrier, gl::ALL_BARRIER_BITS);
 
 pub struct Context {
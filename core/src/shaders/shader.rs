@@ -1,4 +1,4 @@
-use std::{ffi::{CStr, CString}, fs, path::Path, ptr::{null, null_mut}};
+use std::{ffi::{CStr, CString}, fs, path::{Path, PathBuf}, ptr::{null, null_mut}};
 
 use egui_glfw_gl::gl::{self, types};
 
@@ -54,12 +54,41 @@ impl ShaderBuilder {
         Shader::new(result_source, self.shader_type)
     }
 
+    // #include "path" lines are replaced by the file they name, relative to the including file
     pub fn from_file<P: AsRef<Path>>(&self, path: P) -> Result<Shader, ShaderError> {
-        let content = fs::read_to_string(path).map_err(|o| format!("{o}"))?;
+        let content = read_with_includes(path.as_ref(), &mut Vec::new())?;
         self.from_source(&content)
     }
 }
 
+fn read_with_includes(path: &Path, included_from: &mut Vec<PathBuf>) -> Result<String, ShaderError> {
+    let canonical = path.canonicalize().map_err(|o| format!("{}: {o}", path.display()))?;
+    if included_from.contains(&canonical) {
+        return Err(format!("{}: includes itself", path.display()));
+    }
+    let content = fs::read_to_string(path).map_err(|o| format!("{}: {o}", path.display()))?;
+    included_from.push(canonical);
+
+    let mut result = String::new();
+    for l in content.lines() {
+        let Some(include) = l.trim_start().strip_prefix("#include") else {
+            result += l;
+            result += "\n";
+            continue;
+        };
+
+        let name = include.trim()
+            .strip_prefix('"')
+            .and_then(|n| n.strip_suffix('"'))
+            .ok_or(format!("{}: malformed include '{}'", path.display(), l.trim()))?;
+        let included = path.parent().unwrap_or(Path::new("")).join(name);
+        result += &read_with_includes(&included, included_from)?;
+    }
+
+    included_from.pop();
+    Ok(result)
+}
+
 impl Shader {
     pub fn new<Source: AsRef<str>>(source: Source, shader_type: types::GLenum) -> Result<Shader, ShaderError> {
         let id = GL!(gl::CreateShader(shader_type));
@@ -1,4 +1,5 @@
 use std::{ffi::{c_void, CString}, path::Path, ptr::NonNull, slice::from_raw_parts};
+use egui_glfw_gl::gl;
 use stb_image::stb_image;
 use ::stb_image::stb_image::{stbi_load, stbi_set_flip_vertically_on_load};
 
@@ -11,6 +12,28 @@ pub struct ImageFormat {
     pub data_type: u32,
 }
 
+impl ImageFormat {
+    pub fn channels(&self) -> usize {
+        match self.format {
+            gl::RED | gl::GREEN | gl::BLUE | gl::RED_INTEGER | gl::GREEN_INTEGER | gl::BLUE_INTEGER | 
+            gl::DEPTH_COMPONENT | gl::STENCIL_INDEX => 1,
+            gl::RG | gl::RG_INTEGER | gl::DEPTH_STENCIL => 2,
+            gl::RGB | gl::BGR | gl::RGB_INTEGER | gl::BGR_INTEGER => 3,
+            _ => 4,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self.format, 
+            gl::RED_INTEGER | gl::GREEN_INTEGER | gl::BLUE_INTEGER | gl::RG_INTEGER | 
+            gl::RGB_INTEGER | gl::BGR_INTEGER | gl::RGBA_INTEGER | gl::BGRA_INTEGER)
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self.data_type, gl::BYTE | gl::SHORT | gl::INT | gl::FLOAT | gl::HALF_FLOAT)
+    }
+}
+
 pub trait ImageProvider2D {
     fn description(&self) -> ImageFormat;
     fn width(&self) -> i32;
@@ -34,6 +57,19 @@ pub struct GlImage<'a> {
 impl Image {
     pub fn from_file<P: AsRef<Path>>(path: P) ->
         Result<Image, String> 
+    {
+        Self::load(path, 0)
+    }
+
+    // one byte of luminance per pixel whatever the file stores
+    pub fn grayscale_from_file<P: AsRef<Path>>(path: P) ->
+        Result<Image, String> 
+    {
+        Self::load(path, 1)
+    }
+
+    fn load<P: AsRef<Path>>(path: P, desired_channels: i32) ->
+        Result<Image, String> 
     {
         let path = path.as_ref().canonicalize()
             .map_err(|e| format!("{e}"))?;
@@ -45,10 +81,13 @@ impl Image {
         let mut num_chanels = 0;
         let image_data = unsafe {
             stbi_set_flip_vertically_on_load(1);
-            stbi_load(c_path.as_ptr(), &mut width, &mut height, &mut num_chanels, 0)
+            stbi_load(c_path.as_ptr(), &mut width, &mut height, &mut num_chanels, desired_channels)
         }; 
 
         let image_data = NonNull::new(image_data).ok_or("image is null")?;
+        if desired_channels > 0 {
+            num_chanels = desired_channels;
+        }
 
         let image_data = unsafe {
             from_raw_parts::<u8>(image_data.as_ptr(), (width * height * num_chanels) as usize)
@@ -1,5 +1,5 @@
 
-use std::{marker::PhantomData, ptr::null};
+use std::{ffi::c_void, marker::PhantomData, mem::size_of, ptr::null};
 
 use egui_glfw_gl::{egui::Image, gl};
 use glam::{ivec3, IVec3};
@@ -9,6 +9,16 @@ use crate::{GL, OpenglAlias};
 use super::{image_provider::{ImageFormat, ImageProvider2D}, TextureUnit};
 
 
+// runs f with a pixel store parameter (like UNPACK_ALIGNMENT) set to value, the previous value is restored afterwards
+pub fn with_pixel_store<R>(parameter: u32, value: i32, f: impl FnOnce() -> R) -> R {
+    let mut previous = 0;
+    GL!(gl::GetIntegerv(parameter, &mut previous));
+    GL!(gl::PixelStorei(parameter, value));
+    let result = f();
+    GL!(gl::PixelStorei(parameter, previous));
+    result
+}
+
 enum TextureBindType {
     None,
     Texture(TextureUnit),
@@ -177,6 +187,100 @@ impl Texture {
         GL!(gl::GenerateTextureMipmap(self.id));
     }
 
+    pub fn texel_count(&self) -> usize {
+        (self.dimentions.x * self.dimentions.y * self.dimentions.z) as usize
+    }
+
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn size_at_lod(&self, lod: i32) -> IVec3 {
+        let size = IVec3::new(self.dimentions.x >> lod, self.dimentions.y >> lod, self.dimentions.z >> lod);
+        match self.texture_target {
+            gl::TEXTURE_1D => ivec3(size.x.max(1), 1, 1),
+            gl::TEXTURE_2D => ivec3(size.x.max(1), size.y.max(1), 1),
+            _ => size.max(IVec3::ONE),
+        }
+    }
+
+    fn get_sub_image(&self, lod: i32, offset: IVec3, size: IVec3, data_type: u32, bytes: usize, ptr: *mut c_void) {
+        with_pixel_store(gl::PACK_ALIGNMENT, 1, || GL!(gl::GetTextureSubImage(
+            self.id, 
+            lod, 
+            offset.x, offset.y, offset.z, 
+            size.x, size.y, size.z, 
+            self.format.format, 
+            data_type, 
+            bytes as i32, 
+            ptr)));
+    }
+
+    fn set_sub_image(&mut self, lod: i32, offset: IVec3, size: IVec3, data_type: u32, ptr: *const c_void) {
+        with_pixel_store(gl::UNPACK_ALIGNMENT, 1, || match self.texture_target {
+            gl::TEXTURE_1D => GL!(gl::TextureSubImage1D(
+                self.id, lod, offset.x, size.x, 
+                self.format.format, data_type, ptr)),
+            gl::TEXTURE_2D => GL!(gl::TextureSubImage2D(
+                self.id, lod, offset.x, offset.y, size.x, size.y, 
+                self.format.format, data_type, ptr)),
+            _ => GL!(gl::TextureSubImage3D(
+                self.id, lod, offset.x, offset.y, offset.z, size.x, size.y, size.z, 
+                self.format.format, data_type, ptr)),
+        });
+    }
+
+    // whole texture at the lod of its format, channels of a texel are stored next to each other
+    pub fn read_f32(&self) -> Vec<f32> {
+        self.read_region_f32(self.format.lod, IVec3::ZERO, self.size_at_lod(self.format.lod))
+    }
+
+    pub fn write_f32(&mut self, data: &[f32]) {
+        self.write_region_f32(self.format.lod, IVec3::ZERO, self.size_at_lod(self.format.lod), data)
+    }
+
+    // region is given by its first texel and size, for 1d and 2d textures extra components must be 0 and 1.
+    // integer formats are converted to and from f32 on the cpu
+    pub fn read_region_f32(&self, lod: i32, offset: IVec3, size: IVec3) -> Vec<f32> {
+        let len = (size.x * size.y * size.z) as usize * self.format.channels();
+        if !self.format.is_integer() {
+            let mut out = vec![0f32; len];
+            self.get_sub_image(lod, offset, size, gl::FLOAT, len * size_of::<f32>(), out.as_mut_ptr() as *mut c_void);
+            return out;
+        }
+
+        if self.format.is_signed() {
+            let mut out = vec![0i32; len];
+            self.get_sub_image(lod, offset, size, gl::INT, len * size_of::<i32>(), out.as_mut_ptr() as *mut c_void);
+            out.into_iter().map(|v| v as f32).collect()
+        }
+        else {
+            let mut out = vec![0u32; len];
+            self.get_sub_image(lod, offset, size, gl::UNSIGNED_INT, len * size_of::<u32>(), out.as_mut_ptr() as *mut c_void);
+            out.into_iter().map(|v| v as f32).collect()
+        }
+    }
+
+    pub fn write_region_f32(&mut self, lod: i32, offset: IVec3, size: IVec3, data: &[f32]) {
+        assert!(data.len() == (size.x * size.y * size.z) as usize * self.format.channels());
+        if !self.format.is_integer() {
+            self.set_sub_image(lod, offset, size, gl::FLOAT, data.as_ptr() as *const c_void);
+        }
+        else if self.format.is_signed() {
+            let data: Vec<i32> = data.iter().map(|v| v.round() as i32).collect();
+            self.set_sub_image(lod, offset, size, gl::INT, data.as_ptr() as *const c_void);
+        }
+        else {
+            let data: Vec<u32> = data.iter().map(|v| v.round().max(0.) as u32).collect();
+            self.set_sub_image(lod, offset, size, gl::UNSIGNED_INT, data.as_ptr() as *const c_void);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        self.bind = TextureBindType::None;
+        GL!(gl::DeleteTextures(1, &self.id));
+    }
+
     // pub fn unbind(&mut self) {
     //     match self.bind {
     //         TextureBindType::None => return,
@@ -362,6 +466,24 @@ impl TextureBuilder<TexType2d>  {
     }
 }
 
+impl TextureBuilder<TexType1d> {
+    pub fn empty(mut self, width: i32, description: ImageFormat) -> Texture {
+        self.tex.format = description;
+        GL!(gl::TexImage1D(
+            self.tex.texture_target, 
+            description.lod,
+            description.internal_format as i32,
+            width,
+            0,
+            description.format,
+            description.data_type,
+            null()
+        ));
+        self.tex.dimentions = ivec3(width, 1, 1);
+        build(self)
+    }
+}
+
 impl TextureBuilder<TexType3d> {
     pub fn empty(mut self, width: i32, height: i32, depth: i32, description: ImageFormat) -> Texture {
         self.tex.format = description;
@@ -0,0 +1,8 @@
+// shared by the brush shaders and the brush cursor, the including shader declares
+// uniform sampler1D falloff_curve;
+
+// brush falloff curve at the distance from the center relative to the radius
+float Falloff(float len) {
+    float size = float(textureSize(falloff_curve, 0));
+    return texture(falloff_curve, (clamp(len, 0., 1.) * (size - 1.) + 0.5) / size).r;
+}
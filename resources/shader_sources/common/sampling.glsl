@@ -0,0 +1,32 @@
+// shared by the brush shaders, the including shader declares
+// layout(r32f) readonly uniform image3D source;
+
+// field value at the texel, clamped to the image
+float Sample(ivec3 id, ivec3 im_size) {
+    return imageLoad(source, clamp(id, ivec3(0), im_size - ivec3(1))).x;
+}
+
+// trilinear interpolation between the texels around the position in texel units
+float SampleLinear(vec3 position, ivec3 im_size) {
+    ivec3 base = ivec3(floor(position));
+    vec3 t = position - vec3(base);
+
+    float x00 = mix(Sample(base, im_size), Sample(base + ivec3(1, 0, 0), im_size), t.x);
+    float x10 = mix(Sample(base + ivec3(0, 1, 0), im_size), Sample(base + ivec3(1, 1, 0), im_size), t.x);
+    float x01 = mix(Sample(base + ivec3(0, 0, 1), im_size), Sample(base + ivec3(1, 0, 1), im_size), t.x);
+    float x11 = mix(Sample(base + ivec3(0, 1, 1), im_size), Sample(base + ivec3(1, 1, 1), im_size), t.x);
+
+    return mix(mix(x00, x10, t.y), mix(x01, x11, t.y), t.z);
+}
+
+// central difference gradient of the field, in field units per texel
+vec3 Gradient(ivec3 id, ivec3 im_size) {
+    ivec3 x = ivec3(1, 0, 0);
+    ivec3 y = ivec3(0, 1, 0);
+    ivec3 z = ivec3(0, 0, 1);
+    float dx = Sample(id + x, im_size) - Sample(id - x, im_size);
+    float dy = Sample(id + y, im_size) - Sample(id - y, im_size);
+    float dz = Sample(id + z, im_size) - Sample(id - z, im_size);
+
+    return vec3(dx, dy, dz) * 0.5;
+}
@@ -0,0 +1,23 @@
+#version 430 core
+
+uniform sampler1D falloff_curve;
+uniform vec4 color;
+in vec2 local_position;
+out vec4 FragColor;
+
+#include "../common/falloff.glsl"
+
+void main()
+{
+    float len = length(local_position);
+    if (len > 1.) {
+        discard;
+    }
+
+    // ring about two pixels wide at the brush edge, the inside is shaded by the falloff
+    float ring_width = 2. * fwidth(len);
+    float ring = smoothstep(1. - ring_width, 1. - ring_width * 0.5, len);
+    float alpha = max(ring, Falloff(len) * 0.35);
+
+    FragColor = vec4(color.rgb, color.a * alpha);
+}
@@ -0,0 +1,14 @@
+#version 430 core
+
+uniform mat4 view;
+uniform mat4 projection;
+// brush transform, the unit disc in its local xy plane covers the brush
+uniform mat4 model;
+layout (location = 0) in vec2 aPos;
+out vec2 local_position;
+
+void main()
+{
+    local_position = aPos;
+    gl_Position = projection * view * model * vec4(aPos, 0., 1.);
+}
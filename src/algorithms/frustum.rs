@@ -0,0 +1,26 @@
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+
+// planes point inside, a point p is inside a plane when dot(plane.xyz, p) + plane.w >= 0
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // extracts planes from a projection * view matrix with opengl clip space (-w..w on every axis)
+    pub fn from_matrix(matrix: Mat4) -> Frustum {
+        let (r0, r1, r2, r3) = (matrix.row(0), matrix.row(1), matrix.row(2), matrix.row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2]
+            .map(|p| p / p.xyz().length());
+        Frustum { planes }
+    }
+
+    // conservative: boxes near frustum corners may pass even if they are outside
+    pub fn intersects_box(&self, corner: Vec3, size: Vec3) -> bool {
+        self.planes.iter().all(|p| {
+            let normal = p.xyz();
+            let farthest = corner + Vec3::select(normal.cmpge(Vec3::ZERO), size, Vec3::ZERO);
+            normal.dot(farthest) + p.w >= 0.
+        })
+    }
+}
@@ -5,6 +5,7 @@ pub mod raycast;
 pub mod transform;
 pub mod grid_line_intersection;
 pub mod cordinates;
+pub mod frustum;
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Triangle(Vec3, Vec3, Vec3);
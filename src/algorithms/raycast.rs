@@ -20,6 +20,13 @@ impl Ray {
     }
 }
 
+// point where a ray meets the surface and the normal there, facing back towards the ray origin
+#[derive(Debug, Clone, Copy)]
+pub struct RaycastHit {
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
 #[derive(Debug)]
 pub struct RaycastResult {
     pub chunk_cord: IVec3,
@@ -34,7 +41,7 @@ pub enum IntersectionResult {
     Double(Vec3, Vec3)
 }
 
-fn ray_plane_intersection(position: Vec3, normal: Vec3, ray: Ray) -> Option<Vec3> {
+pub fn ray_plane_intersection(position: Vec3, normal: Vec3, ray: Ray) -> Option<Vec3> {
     let to_plane = position - ray.origin;
     let to_plane_proj = normal * Vec3::dot(normal, to_plane);
     let dir_proj_dot = Vec3::dot(to_plane_proj, ray.direction);
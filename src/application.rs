@@ -11,6 +11,7 @@ use self::app_logick::{ExecutrionLogick, Parameters};
 
 mod app_logick;
 mod cunks;
+mod mesh_io;
 mod support;
 
 pub struct EguiContext {
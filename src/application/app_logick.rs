@@ -1,6 +1,7 @@
 use core::context::synchronization_context::SynchronizationContext;
 use core::GL;
 use std::default;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -17,6 +18,7 @@ use egui_glfw_gl::egui;
 use glam::vec4;
 use glam::BVec3;
 use glam::IVec3;
+use glam::Mat4;
 use glam::Quat;
 use glam::Vec2;
 use glam::Vec3;
@@ -27,18 +29,34 @@ use glam::Vec4Swizzles;
 
 use crate::algorithms::camera::Camera;
 use crate::algorithms::camera::perspective::PerspectiveCamera;
+use crate::algorithms::raycast::Ray;
+use crate::algorithms::raycast::RaycastHit;
 use crate::application::support::brush::circle_bruhs::CircleBrush;
 use crate::application::support::brush::Brush;
 use crate::application::support::brush::BrushApplicationParameters;
 
-use super::cunks::field::Field;
+use super::cunks::document::FieldDocument;
+use super::cunks::field::{Field, CHUNK_RESOLUTIONS};
+use super::mesh_io;
+use super::mesh_io::glb;
+use super::mesh_io::MeshFormat;
 use super::support::bounds::Bounds;
 use super::support::brush::circle_bruhs::InverseCircleBrush;
 use super::support::brush::flatten_brush::FlattenBrush;
+use super::support::brush::grab_brush::GrabBrush;
+use super::support::brush::inflate_brush::{DeflateBrush, InflateBrush};
+use super::support::brush::pinch_brush::{CreaseBrush, PinchBrush};
+use super::support::brush::noise_brush::NoiseBrush;
+use super::support::brush::stamp_brush::StampBrush;
+use super::support::brush::smooth_brush::SmoothBrush;
 use super::support::brush::BrushSettings;
+use super::support::brush_cursor::BrushCursor;
+use super::support::brush::preset::{BrushPreset, BrushValues, PresetLibrary};
 use super::support::camera_ref::CameraRef;
-use super::support::debugger::Debugger;
+use super::support::debugger::{DebugPrimitive, Debugger};
 use super::support::shaders::shaders_loader::ShaderStorage;
+use super::support::stroke::{Stabilizer, StabilizerMode, StrokePath};
+use super::support::symmetry::AXIS_NAMES;
 
 
 struct DebugSettings {
@@ -54,6 +72,69 @@ impl Default for DebugSettings {
             parity: BVec3 { x: true, y: true, z: true } }
     }
 }
+struct StrokeSettings {
+    // distance between dabs relative to the brush radius
+    spacing: f32,
+    // brush local z follows the surface normal under the cursor
+    orient_to_surface: bool,
+    // brush local x follows the stroke direction
+    follow_stroke: bool,
+    // brush scale along its local axes, z is along the normal once oriented
+    scale: Vec3,
+}
+
+impl Default for StrokeSettings {
+    fn default() -> Self {
+        Self { spacing: 0.25, orient_to_surface: true, follow_stroke: false, scale: Vec3::ONE }
+    }
+}
+
+struct LodSettings {
+    enabled: bool,
+    min_pixels_per_cube: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self { enabled: true, min_pixels_per_cube: 3. }
+    }
+}
+
+struct DocumentSettings {
+    path: String,
+    export_path: String,
+    export_format: MeshFormat,
+    merge_chunks: bool,
+    import_path: String,
+    import_size: f32,
+    import_replace: bool,
+    resolution: i32,
+    status: String,
+}
+
+impl Default for DocumentSettings {
+    fn default() -> Self {
+        Self { 
+            path: "sculpt.blob".to_owned(), 
+            export_path: "sculpt.obj".to_owned(),
+            export_format: MeshFormat::Obj,
+            merge_chunks: false,
+            import_path: "base.obj".to_owned(),
+            import_size: 0.8,
+            import_replace: true,
+            resolution: DEFAULT_CHUNK_RESOLUTION,
+            status: String::new() 
+        }
+    }
+}
+
+struct PresetSettings {
+    library: PresetLibrary,
+    selected: Option<usize>,
+    // name used by save and rename
+    name: String,
+}
+
 pub struct ExecutrionLogick {
     // command_buffer: Buffer,
     camera: PerspectiveCamera,
@@ -66,6 +147,17 @@ pub struct ExecutrionLogick {
     brushes: Vec<Arc<Mutex<dyn BrushSettings>>>,
     selected_brush: usize,
     debug: DebugSettings,
+    document: DocumentSettings,
+    presets: PresetSettings,
+    lod: LodSettings,
+    stroke: StrokeSettings,
+    stroke_path: StrokePath,
+    stabilizer: Stabilizer,
+    is_stroke_active: bool,
+    grabbed_brush: Option<Arc<Mutex<dyn BrushSettings>>>,
+    brush_cursor: BrushCursor,
+    // surface under the pointer, where the brush cursor is drawn
+    hover: Option<RaycastHit>,
     instant: Instant,
     // image: Image
     // programm: ShaderProgramm,
@@ -83,8 +175,9 @@ pub const fn ceil_div(val: usize, divider: usize) -> usize {
 
 pub const BLOCKY: bool = false;
 pub const FLAT_SHADING: bool = false;
-pub const CHUNK_SIZE: i32 = 48;
-pub const NUM_OF_CUBES: IVec3 = IVec3 { x: CHUNK_SIZE, y: CHUNK_SIZE, z: CHUNK_SIZE };
+const DEFAULT_CHUNK_RESOLUTION: i32 = 48;
+
+const PRESETS_PATH: &str = "brush_presets.txt";
 
 const FPS: usize = 60;
 
@@ -116,19 +209,36 @@ impl ExecutrionLogick {
 
         let debugger = Debugger::new();
 
-        let field = Field::new(sync_context.clone(), programm_storage.clone(), debugger.clone());
+        let field = Field::new(
+            sync_context.clone(), 
+            programm_storage.clone(), 
+            debugger.clone(), 
+            IVec3::splat(DEFAULT_CHUNK_RESOLUTION));
 
         let brushes = brush_vec![
             CircleBrush::new(programm_storage.clone()),
             InverseCircleBrush::new(programm_storage.clone()),
-            
-            // not working properly :(
             FlattenBrush::new(sync_context.clone(), programm_storage.clone()),
+            SmoothBrush::new(sync_context.clone(), programm_storage.clone()),
+            GrabBrush::new(sync_context.clone(), programm_storage.clone()),
+            InflateBrush::new(sync_context.clone(), programm_storage.clone()),
+            DeflateBrush::new(sync_context.clone(), programm_storage.clone()),
+            PinchBrush::new(sync_context.clone(), programm_storage.clone()),
+            CreaseBrush::new(sync_context.clone(), programm_storage.clone()),
+            NoiseBrush::new(programm_storage.clone()),
+            StampBrush::new(programm_storage.clone()),
         ];
 
         // let brushes = brush_vec!(CircleBrush::new(programm_storage.clone()),
         //         InverseCircleBrush::new(programm_storage.clone()),
         // );
+
+        let mut document = DocumentSettings::default();
+        let library = PresetLibrary::load(PRESETS_PATH).unwrap_or_else(|e| {
+            document.status = format!("presets not loaded: {e}");
+            PresetLibrary::empty(PRESETS_PATH)
+        });
+
         ExecutrionLogick { 
 
             debugger,
@@ -140,6 +250,16 @@ impl ExecutrionLogick {
             selected_brush: 0,
             // ao_upper_edge: 0.1,
             debug: Default::default(),
+            document,
+            presets: PresetSettings { library, selected: None, name: String::new() },
+            lod: Default::default(),
+            stroke: Default::default(),
+            stroke_path: StrokePath::new(),
+            stabilizer: Stabilizer::new(),
+            is_stroke_active: false,
+            grabbed_brush: None,
+            brush_cursor: BrushCursor::new(programm_storage.clone()),
+            hover: None,
             sync_context,
             instant: Instant::now(),
             programm_storage,
@@ -194,7 +314,7 @@ impl ExecutrionLogick {
             let pos = self.camera.transform.position() + Vec3::Y * speed;
             self.camera.transform.set_position(pos);
         }
-        if input.key_down(egui::Key::Z) {
+        if input.key_down(egui::Key::Z) && !input.modifiers.command {
             let pos = self.camera.transform.position() + Vec3::Y * -speed;
             self.camera.transform.set_position(pos);
         }
@@ -217,34 +337,227 @@ impl ExecutrionLogick {
         }
         
 
-        if !egui_ctx.is_pointer_over_area() && 
-            input.pointer.button_down(egui::PointerButton::Primary) {
+        if !egui_ctx.wants_keyboard_input() && input.modifiers.command && input.key_pressed(egui::Key::Z) {
+            self.end_stroke();
+            if input.modifiers.shift {
+                self.field.redo();
+            }
+            else {
+                self.field.undo();
+            }
+        }
 
-            let hit = if let Some(mouse_pos) = input.pointer.hover_pos() {
+        if self.is_stroke_active && !input.pointer.button_down(egui::PointerButton::Primary) {
+            self.end_stroke();
+        }
+
+        let ray = input.pointer.hover_pos()
+            .filter(|_| !egui_ctx.is_pointer_over_area())
+            .map(|mouse_pos| {
                 let size = vec2(input.screen_rect.width(), input.screen_rect.height());
                 let viewport =  vec2(mouse_pos.x, mouse_pos.y) / size;
-                let ray = self.camera.viewport_point_to_ray(vec3(viewport.x, 1. - viewport.y, 0.));
-        
-                self.field.raycast(ray)
+                self.camera.viewport_point_to_ray(vec3(viewport.x, 1. - viewport.y, 0.))
+            });
+        self.hover = None;
+
+        if !egui_ctx.is_pointer_over_area() && 
+            input.pointer.button_down(egui::PointerButton::Primary) {
+
+            if !self.is_stroke_active {
+                self.is_stroke_active = true;
+                self.field.begin_stroke();
+            }
+
+            let settings = self.brushes[self.selected_brush].clone();
+            if settings.lock().unwrap().grabs() {
+                self.drag(settings, ray);
+                return;
+            }
+
+            let hit = ray.and_then(|ray| self.field.raycast(ray));
+            self.hover = hit;
+
+            if let Some(hit) = hit {
+                let pointer = hit.point;
+                let position = self.stabilizer.update(pointer, self.delta_time_ratio);
+                if self.stabilizer.mode != StabilizerMode::Off {
+                    self.debugger.draw_overlay(DebugPrimitive::Line { from: pointer, to: position }, Color32::WHITE, 1.);
+                    self.debugger.draw_overlay(DebugPrimitive::Point(position), Color32::WHITE, 1.);
+                }
+
+                let spacing = settings.lock().unwrap().radius() * self.stroke.spacing;
+
+                // dabs are spaced along the path, so their strength doesn't depend on the frame time
+                let dabs = self.stroke_path.advance(position, spacing);
+                let tangent = self.stroke_path.direction().filter(|_| self.stroke.follow_stroke);
+                let brushes: Vec<_> = dabs.into_iter()
+                    .map(|dab| Brush::new(settings.clone(), self.dab_parameters(dab, hit.normal, tangent)))
+                    .collect();
+
+                if !brushes.is_empty() {
+                    self.field.apply_brushes(&brushes);
+                }
             }
             else {
-                None
-            };
+                self.stroke_path.break_path();
+                self.stabilizer.reset();
+            }
+        }
+        else {
+            self.hover = ray.and_then(|ray| self.field.raycast(ray));
+        }
+    }
 
-            if let Some(position) = hit {
+    fn dab_parameters(&self, position: Vec3, normal: Vec3, tangent: Option<Vec3>) -> BrushApplicationParameters {
+        let parameters = BrushApplicationParameters::new(position, 1.).with_scale(self.stroke.scale);
+        match self.stroke.orient_to_surface {
+            true => parameters.with_normal(normal, tangent),
+            false => parameters.with_surface_normal(normal),
+        }
+    }
 
-                let settings = BrushApplicationParameters::new(
-                    position, self.delta_time_ratio
-                );
+    // the brush and its symmetry copies at the hovered point, depth tested against the sculpt
+    fn draw_brush_cursor(&mut self) {
+        let Some(hit) = self.hover else {
+            return;
+        };
+
+        let tangent = self.stroke_path.direction().filter(|_| self.stroke.follow_stroke);
+        let mut parameters = self.dab_parameters(hit.point, hit.normal, tangent);
+        if !self.stroke.orient_to_surface {
+            // an unoriented brush is still drawn lying on the surface
+            parameters = parameters.with_normal(hit.normal, None);
+        }
 
-                let brush = Brush::new(
-                    self.brushes[self.selected_brush].clone(),
-                    settings
-                );
+        let settings = self.brushes[self.selected_brush].clone();
+        let matrices: Vec<_> = self.field.symmetry()
+            .replicate(&Brush::new(settings.clone(), parameters))
+            .iter()
+            .map(|b| b.matrix())
+            .collect();
 
-                self.field.apply_brush(&brush);
+        let mut settings = settings.lock().unwrap();
+        self.brush_cursor.draw(&self.camera, &mut *settings, &matrices);
+    }
+
+    // the first hit of a stroke grabs the surface, later frames move the grabbed region with the cursor
+    fn drag(&mut self, settings: Arc<Mutex<dyn BrushSettings>>, ray: Option<Ray>) {
+        let Some(ray) = ray else {
+            return;
+        };
+
+        if self.grabbed_brush.is_none() {
+            if let Some(hit) = self.field.raycast(ray) {
+                let dab = self.dab_parameters(hit.point, hit.normal, None);
+                settings.lock().unwrap().begin_grab(hit.point, self.camera.transform.forward(), dab);
+                self.grabbed_brush = Some(settings);
             }
+            return;
         }
+
+        let parameters = settings.lock().unwrap().drag(ray);
+        let brushes: Vec<_> = parameters.into_iter()
+            .map(|p| Brush::new(settings.clone(), p))
+            .collect();
+
+        if !brushes.is_empty() {
+            self.field.apply_brushes(&brushes);
+        }
+    }
+
+    fn end_stroke(&mut self) {
+        if self.is_stroke_active {
+            self.is_stroke_active = false;
+            self.stroke_path.break_path();
+            self.stabilizer.reset();
+            if let Some(brush) = self.grabbed_brush.take() {
+                brush.lock().unwrap().end_grab();
+            }
+            self.field.end_stroke();
+        }
+    }
+
+    fn save_document(&mut self) -> Result<(), String> {
+        self.field.to_document().save(&self.document.path)
+    }
+
+    fn load_document(&mut self) -> Result<(), String> {
+        let document = FieldDocument::load(&self.document.path)?;
+        self.field.load_document(&document)?;
+        self.document.resolution = self.field.num_of_cubes().max_element();
+        Ok(())
+    }
+
+    // switches to the brush the preset was saved from and loads its values
+    fn load_preset(&mut self, index: usize) -> Result<(), String> {
+        let preset = &self.presets.library.presets()[index];
+        let brush = self.brushes.iter()
+            .position(|b| b.lock().unwrap().brush_name() == preset.brush)
+            .ok_or_else(|| format!("unknown brush {}", preset.brush))?;
+
+        self.brushes[brush].lock().unwrap().load_settings(&preset.values);
+        self.selected_brush = brush;
+        self.presets.selected = Some(index);
+        self.presets.name = preset.name.clone();
+        Ok(())
+    }
+
+    fn save_preset(&mut self) -> Result<(), String> {
+        if self.presets.name.trim().is_empty() {
+            return Err("preset needs a name".to_owned());
+        }
+        let brush = self.brushes[self.selected_brush].lock().unwrap();
+        let mut values = BrushValues::default();
+        brush.save_settings(&mut values);
+
+        let name = self.presets.name.trim().to_owned();
+        self.presets.library.store(BrushPreset { name: name.clone(), brush: brush.brush_name().to_owned(), values });
+        self.presets.selected = self.presets.library.presets().iter().position(|p| p.name == name);
+        self.presets.library.save()
+    }
+
+    fn rename_preset(&mut self) -> Result<(), String> {
+        let index = self.presets.selected.ok_or("no preset selected")?;
+        if self.presets.name.trim().is_empty() {
+            return Err("preset needs a name".to_owned());
+        }
+        self.presets.library.rename(index, self.presets.name.trim())?;
+        self.presets.library.save()
+    }
+
+    fn delete_preset(&mut self) -> Result<(), String> {
+        let index = self.presets.selected.take().ok_or("no preset selected")?;
+        self.presets.library.delete(index);
+        self.presets.library.save()
+    }
+
+    // mesh is scaled to fit import_size along its longest side and centered in the first chunk
+    fn import_mesh(&mut self) -> Result<usize, String> {
+        let mut mesh = mesh_io::import(&self.document.import_path)?;
+        let (min, max) = mesh.bounds().unwrap();
+        let extent = (max - min).max_element();
+        if extent <= 0. {
+            return Err("mesh is flat".to_owned());
+        }
+        let scale = self.document.import_size / extent;
+        mesh.transform(Mat4::from_scale_rotation_translation(
+            Vec3::splat(scale), 
+            Quat::IDENTITY, 
+            Vec3::splat(0.5) - (min + max) * 0.5 * scale));
+        Ok(self.field.import_mesh(&mesh, self.document.import_replace))
+    }
+
+    fn export_mesh(&mut self) -> Result<usize, String> {
+        if self.document.export_format == MeshFormat::Glb && !self.document.merge_chunks {
+            let nodes = self.field.collect_chunk_meshes();
+            let mut bytes = Vec::new();
+            glb::write(&nodes, &mut bytes)?;
+            std::fs::write(&self.document.export_path, bytes).map_err(|e| format!("{e}"))?;
+            return Ok(nodes.iter().map(|n| n.mesh.triangle_count()).sum());
+        }
+        let mesh = self.field.collect_mesh();
+        mesh.export(&self.document.export_path, self.document.export_format)?;
+        Ok(mesh.triangle_count())
     }
 
     pub fn draw(&mut self, params: Parameters) {
@@ -255,6 +568,10 @@ impl ExecutrionLogick {
         
         
         self.camera.set_aspect_ratio(params.height as f32 / params.width as f32);
+        self.field.update_lods(
+            &self.camera, 
+            params.height as f32, 
+            self.lod.enabled.then_some(self.lod.min_pixels_per_cube));
         
         if !self.debug.debug {
             self.field.draw(&self.camera);
@@ -271,6 +588,7 @@ impl ExecutrionLogick {
             }
         }
 
+        self.draw_brush_cursor();
 
         GL!(gl::Disable(gl::DEPTH_TEST));
     }
@@ -282,24 +600,243 @@ impl ExecutrionLogick {
             // ui.add(egui::Slider::new(&mut fov, 1.0..=179.).text("fov"));
             // self.camera.set_fov(fov);
 
-            egui::ComboBox::from_label("Select brush")
-                .selected_text(format!("{:?}", self.brushes[self.selected_brush].lock().unwrap().brush_name()))
-                .show_ui(ui, |ui| {
-                    for i in 0..self.brushes.len() {
-                        ui.selectable_value(&mut self.selected_brush, 
-                            i, self.brushes[i].lock().unwrap().brush_name());
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Select brush")
+                    .selected_text(format!("{:?}", self.brushes[self.selected_brush].lock().unwrap().brush_name()))
+                    .show_ui(ui, |ui| {
+                        for i in 0..self.brushes.len() {
+                            ui.selectable_value(&mut self.selected_brush, 
+                                i, self.brushes[i].lock().unwrap().brush_name());
+                        }
+                    }
+                );
+
+                let mut selected = self.presets.selected;
+                let selected_name = selected
+                    .and_then(|i| self.presets.library.presets().get(i))
+                    .map(|p| p.name.as_str())
+                    .unwrap_or("none");
+                egui::ComboBox::from_label("preset")
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        for (i, preset) in self.presets.library.presets().iter().enumerate() {
+                            ui.selectable_value(&mut selected, Some(i), 
+                                format!("{} ({})", preset.name, preset.brush));
+                        }
                     }
+                );
+                if let Some(index) = selected.filter(|_| selected != self.presets.selected) {
+                    if let Err(e) = self.load_preset(index) {
+                        self.document.status = format!("preset not loaded: {e}");
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("preset name");
+                ui.text_edit_singleline(&mut self.presets.name);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("save preset").clicked() {
+                    self.document.status = match self.save_preset() {
+                        Ok(()) => format!("saved preset {}", self.presets.name.trim()),
+                        Err(e) => format!("preset not saved: {e}"),
+                    };
                 }
-            );
+                if ui.button("rename").clicked() {
+                    self.document.status = match self.rename_preset() {
+                        Ok(()) => format!("renamed preset to {}", self.presets.name.trim()),
+                        Err(e) => format!("preset not renamed: {e}"),
+                    };
+                }
+                if ui.button("delete").clicked() {
+                    self.document.status = match self.delete_preset() {
+                        Ok(()) => "deleted preset".to_owned(),
+                        Err(e) => format!("preset not deleted: {e}"),
+                    };
+                }
+            });
 
             ui.add_space(10.);
 
 
             ui.label(self.brushes[self.selected_brush].lock().unwrap().brush_name());
             self.brushes[self.selected_brush].lock().unwrap().display_ui(ui);
+            ui.add(egui::Slider::new(&mut self.stroke.spacing, 0.05..=1.).text("spacing"));
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.stroke.orient_to_surface, "orient to surface");
+                if self.stroke.orient_to_surface {
+                    ui.checkbox(&mut self.stroke.follow_stroke, "roll along stroke");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("brush scale");
+                ui.add(egui::DragValue::new(&mut self.stroke.scale.x).speed(0.01).clamp_range(0.1..=4.).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut self.stroke.scale.y).speed(0.01).clamp_range(0.1..=4.).prefix("y: "));
+                ui.add(egui::DragValue::new(&mut self.stroke.scale.z).speed(0.01).clamp_range(0.1..=4.).prefix("z: "));
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("stabilizer")
+                    .selected_text(self.stabilizer.mode.name())
+                    .show_ui(ui, |ui| {
+                        for mode in StabilizerMode::ALL {
+                            ui.selectable_value(&mut self.stabilizer.mode, mode, mode.name());
+                        }
+                    }
+                );
+            });
+            match self.stabilizer.mode {
+                StabilizerMode::Off => (),
+                StabilizerMode::LazyRadius => {
+                    ui.add(egui::Slider::new(&mut self.stabilizer.lazy_radius, 0.0..=0.3).text("lazy radius"));
+                },
+                StabilizerMode::Exponential => {
+                    ui.add(egui::Slider::new(&mut self.stabilizer.smoothing, 0.0..=0.98).text("smoothing"));
+                },
+            }
             ui.add_space(10.);    
+
+            let symmetry = self.field.symmetry_mut();
+            ui.horizontal(|ui| {
+                ui.label("mirror");
+                ui.checkbox(&mut symmetry.mirror.x, "x");
+                ui.checkbox(&mut symmetry.mirror.y, "y");
+                ui.checkbox(&mut symmetry.mirror.z, "z");
+            });
+            ui.horizontal(|ui| {
+                ui.label("mirror planes at");
+                ui.add(egui::DragValue::new(&mut symmetry.plane_offset.x).speed(0.01).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut symmetry.plane_offset.y).speed(0.01).prefix("y: "));
+                ui.add(egui::DragValue::new(&mut symmetry.plane_offset.z).speed(0.01).prefix("z: "));
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::Slider::new(&mut symmetry.radial_count, 1..=32).text("radial copies"));
+                egui::ComboBox::from_id_source("radial axis")
+                    .selected_text(AXIS_NAMES[symmetry.radial_axis])
+                    .show_ui(ui, |ui| {
+                        for (axis, name) in AXIS_NAMES.iter().enumerate() {
+                            ui.selectable_value(&mut symmetry.radial_axis, axis, *name);
+                        }
+                    }
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("radial center");
+                ui.add(egui::DragValue::new(&mut symmetry.radial_center.x).speed(0.01).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut symmetry.radial_center.y).speed(0.01).prefix("y: "));
+                ui.add(egui::DragValue::new(&mut symmetry.radial_center.z).speed(0.01).prefix("z: "));
+            });
+            ui.add_space(10.);
+
+            ui.horizontal(|ui| {
+                if ui.button("undo").clicked() {
+                    self.end_stroke();
+                    self.field.undo();
+                }
+                if ui.button("redo").clicked() {
+                    self.end_stroke();
+                    self.field.redo();
+                }
+                let history = self.field.history();
+                ui.label(format!("{} / {} steps, {:.1} MB", 
+                    history.undo_count(), 
+                    history.redo_count(), 
+                    history.byte_size() as f32 / (1024. * 1024.)));
+            });
+            let mut budget_mb = self.field.history().budget() / (1024 * 1024);
+            ui.add(egui::Slider::new(&mut budget_mb, 16..=2048).text("history memory (MB)"));
+            if budget_mb * 1024 * 1024 != self.field.history().budget() {
+                self.field.history_mut().set_budget(budget_mb * 1024 * 1024);
+            }
+            ui.add_space(10.);
+
+            ui.horizontal(|ui| {
+                ui.label("file");
+                ui.text_edit_singleline(&mut self.document.path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("save").clicked() {
+                    self.document.status = match self.save_document() {
+                        Ok(()) => format!("saved {}", self.document.path),
+                        Err(e) => format!("save failed: {e}"),
+                    };
+                }
+                if ui.button("load").clicked() {
+                    self.document.status = match self.load_document() {
+                        Ok(()) => format!("loaded {}", self.document.path),
+                        Err(e) => format!("load failed: {e}"),
+                    };
+                }
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("chunk resolution")
+                    .selected_text(format!("{}", self.document.resolution))
+                    .show_ui(ui, |ui| {
+                        for resolution in CHUNK_RESOLUTIONS {
+                            ui.selectable_value(&mut self.document.resolution, resolution, format!("{resolution}"));
+                        }
+                    }
+                );
+                if ui.button("new sculpt").clicked() {
+                    self.end_stroke();
+                    self.field.reset(IVec3::splat(self.document.resolution));
+                    self.document.status = format!("new sculpt with {} cubes per chunk", self.document.resolution);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("export to");
+                ui.text_edit_singleline(&mut self.document.export_path);
+            });
+            ui.horizontal(|ui| {
+                let previous_format = self.document.export_format;
+                egui::ComboBox::from_id_source("export format")
+                    .selected_text(self.document.export_format.name())
+                    .show_ui(ui, |ui| {
+                        for format in MeshFormat::ALL {
+                            ui.selectable_value(&mut self.document.export_format, format, format.name());
+                        }
+                    }
+                );
+                if previous_format != self.document.export_format {
+                    self.document.export_path = Path::new(&self.document.export_path)
+                        .with_extension(self.document.export_format.extension())
+                        .to_string_lossy().into_owned();
+                }
+                if self.document.export_format == MeshFormat::Glb {
+                    ui.checkbox(&mut self.document.merge_chunks, "merge chunks");
+                }
+                if ui.button("export").clicked() {
+                    self.document.status = match self.export_mesh() {
+                        Ok(triangles) => format!("exported {} triangles to {}", triangles, self.document.export_path),
+                        Err(e) => format!("export failed: {e}"),
+                    };
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("import from");
+                ui.text_edit_singleline(&mut self.document.import_path);
+            });
+            ui.add(egui::Slider::new(&mut self.document.import_size, 0.05..=4.).text("import size"));
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.document.import_replace, "replace sculpt");
+                if ui.button("import").clicked() {
+                    self.document.status = match self.import_mesh() {
+                        Ok(chunks) => format!("imported {} into {} chunks", self.document.import_path, chunks),
+                        Err(e) => format!("import failed: {e}"),
+                    };
+                }
+            });
+            if !self.document.status.is_empty() {
+                ui.label(&self.document.status);
+            }
+            ui.add_space(10.);
                     
             
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.lod.enabled, "level of detail");
+                ui.add(egui::Slider::new(&mut self.lod.min_pixels_per_cube, 1.0..=16.).text("pixels per cube"));
+            });
+            ui.add_space(10.);
+
             let mut new_debug = self.debug.debug;
             ui.checkbox(&mut new_debug, "debug");
             if new_debug != self.debug.debug {
@@ -1,11 +1,11 @@
-use core::{buffers::buffer::{Usage, VertexBuffer}, context::synchronization_context::{AllBarrier, ShaderImageAccessBarrier, ShaderStorageBarrier, SynchronizationContext}, textures::{image_provider::ImageFormat, texture::{FilterMode, MipMapFilterMode, Texture, TextureAccess, WrapMode}, TextureUnit}, GL};
+use core::{buffers::buffer::{Usage, VertexBuffer}, context::synchronization_context::{AllBarrier, CommandBarrier, ShaderImageAccessBarrier, ShaderStorageBarrier, SynchronizationContext, TextureFetchBarrier, TextureUpdateBarrier}, textures::{image_provider::ImageFormat, texture::{FilterMode, MipMapFilterMode, Texture, TextureAccess, WrapMode}, TextureUnit}, GL};
 
 use egui_glfw_gl::{egui::Color32, gl};
 use glam::{ivec3, vec3, IVec3, Mat4, Vec3};
 
-use crate::{algorithms::{camera::Camera, grid_line_intersection::march_grid_by_ray, raycast::{ray_box_intersection, ray_triangle_intersection, IntersectionResult, Ray}}, application::{app_logick::NUM_OF_CUBES, support::{bounds::Bounds, brush::{chunk_to_texture_position, Brush}, debugger::{DebugPrimitive, Debugger}, shaders::{shaders_loader::ShaderStorage, FillCircleProgramm, ModelProgramm, ShadedModelProgramm, ZeroFieldProgramm}, simple_quad::SimpleQuad}}};
+use crate::{algorithms::{camera::Camera, grid_line_intersection::march_grid_by_ray, raycast::{ray_box_intersection, ray_triangle_intersection, IntersectionResult, Ray, RaycastHit}}, application::{support::{bounds::Bounds, brush::{chunk_to_texture_position, Brush}, debugger::{DebugPrimitive, Debugger}, shaders::{shaders_loader::ShaderStorage, FillCircleProgramm, ModelProgramm, ShadedModelProgramm, ZeroFieldProgramm}, simple_quad::SimpleQuad}}};
 
-use super::{collision_shape::{CollisionShape, CollisionShapeDebugView}, field::CHUNK_SCALE_FACTOR, marching_cubes::{block_marcher::BlockCubeMarcher, full_marcher::FullCubeMarcher, CubeMarcher, MarchParameters}, DrawParameters, ModelVertex};
+use super::{history::FieldRegion, collision_shape::{CollisionShape, CollisionShapeDebugView}, field::chunk_scale_factor, marching_cubes::{block_marcher::BlockCubeMarcher, full_marcher::FullCubeMarcher, CubeMarcher, MarchParameters}, DrawParameters, ModelVertex};
 
 #[derive(Uniforms)]
 #[for_shaders("resources/shader_sources/marching_cubes/fill_circle.compute")]
@@ -45,15 +45,15 @@ pub const TEXTURE_SIZE_DELTA_HALVED: Vec3 = Vec3 {
     z: TEXTURE_SIZE_DELTA.z as f32 * 0.5,
 };
 
-pub const TEXTURE_DIM: IVec3 = IVec3 { 
-    x: NUM_OF_CUBES.x + TEXTURE_SIZE_DELTA.x, 
-    y: NUM_OF_CUBES.y + TEXTURE_SIZE_DELTA.y, 
-    z: NUM_OF_CUBES.z + TEXTURE_SIZE_DELTA.z 
-};
+pub fn texture_dim(num_of_cubes: IVec3) -> IVec3 {
+    num_of_cubes + TEXTURE_SIZE_DELTA
+}
 
 
 const BLOCK_WRITE: bool = true;
 
+pub const DEFAULT_SURFACE_LEVEL: f32 = 0.3;
+
 #[derive(Uniforms)]
 #[for_shaders("resources/shader_sources/drawing/display_model.vert", 
               "resources/shader_sources/drawing/display_model.frag")]
@@ -82,31 +82,45 @@ struct ShadedModelDisplayUniform {
 
 
 fn fill_sphere(chunk: &mut Chunk, center_uvw: Vec3) {
+    let tex_dim = chunk.texture_dim();
     chunk.march_parameters.distance_field.bind_image(1, TextureAccess::Write);
 
     chunk.march_parameters.programm_storage.access().get::<FillCircleProgramm>().unwrap()
     .bind().set_uniforms(FillTextureUniforms {
         img_output: 1.into(),
-        center_uvw: chunk_to_texture_position(center_uvw, TEXTURE_DIM),
+        center_uvw: chunk_to_texture_position(center_uvw, tex_dim),
     }).unwrap();
 
     chunk.march_parameters.sync_context.dirty(ShaderImageAccessBarrier);
-    GL!(gl::DispatchCompute((TEXTURE_DIM.x) as u32, 
-        (TEXTURE_DIM.y) as u32, 
-        (TEXTURE_DIM.z) as u32));
+    GL!(gl::DispatchCompute((tex_dim.x) as u32, 
+        (tex_dim.y) as u32, 
+        (tex_dim.z) as u32));
 
     chunk.is_sdf_top_level_dirty = true;
 
-    chunk.march_parameters.dirty_area = Bounds::min_max(IVec3::ZERO, NUM_OF_CUBES);     
+    chunk.march_parameters.dirty_area = Bounds::min_max(IVec3::ZERO, chunk.march_parameters.num_of_cubes);     
+}
+
+fn model_vertex_buffer(num_of_cubes: IVec3) -> VertexBuffer<ModelVertex> {
+    VertexBuffer::from_data(
+        &vec![ModelVertex::default(); (num_of_cubes.x * num_of_cubes.y * num_of_cubes.z * 15) as usize],
+        Usage::dynamic_copy())
+}
+
+fn create_marcher(march_parameters: &MarchParameters) -> Box<dyn CubeMarcher> {
+    if BLOCK_WRITE { 
+        Box::new(BlockCubeMarcher::new(march_parameters))
+    } else { 
+        Box::new(FullCubeMarcher::new(march_parameters))
+    }
 }
 
 impl Chunk {
-    fn uninitialized(sync_context: SynchronizationContext, programm_storage: ShaderStorage, debugger: Debugger) -> Chunk {
-        let model_vertex_buffer = VertexBuffer::from_data(
-            &vec![ModelVertex::default(); (NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z * 15) as usize],
-            Usage::dynamic_copy());
+    fn uninitialized(sync_context: SynchronizationContext, programm_storage: ShaderStorage, debugger: Debugger, num_of_cubes: IVec3) -> Chunk {
+        let model_vertex_buffer = model_vertex_buffer(num_of_cubes);
 
-        let collision_field = CollisionShape::new();
+        let collision_field = CollisionShape::new(num_of_cubes);
+        let tex_dim = texture_dim(num_of_cubes);
 
         let img_format = ImageFormat {
             lod: 0,
@@ -119,14 +133,14 @@ impl Chunk {
             .minification_filter(MipMapFilterMode::TRILINEAR )
             .wrap_mode_x(WrapMode::ClampToEdge)
             .wrap_mode_y(WrapMode::ClampToEdge)
-            // .empty(TEXTURE_DIM.x, TEXTURE_DIM.y, ImageFormat {
+            // .empty(tex_dim.x, tex_dim.y, ImageFormat {
             //     lod: 0,
             //     format: gl::RED,
             //     internal_format: gl::R32F,
             //     data_type: gl::FLOAT
             // });
             .wrap_mode_z(WrapMode::ClampToEdge)
-            .empty(TEXTURE_DIM.x, TEXTURE_DIM.y, TEXTURE_DIM.z, img_format);
+            .empty(tex_dim.x, tex_dim.y, tex_dim.z, img_format);
 
         sync_context.force_sync(AllBarrier);
 
@@ -135,18 +149,16 @@ impl Chunk {
             distance_field: texture,
             programm_storage,
             chunk_scale_factor: Vec3::ONE,
-            num_of_cubes: NUM_OF_CUBES,
+            num_of_cubes,
             model_vertex_buffer,
             collision_field,
-            surface_level: 0.3,
+            surface_level: DEFAULT_SURFACE_LEVEL,
             dirty_area: Bounds::empty(),
+            lod: 0,
+            transition_faces: 0,
         };
 
-        let marcher: Box<dyn CubeMarcher> = if BLOCK_WRITE { 
-            Box::new(BlockCubeMarcher::new(&march_parameters))
-        } else { 
-            Box::new(FullCubeMarcher::new())
-        };
+        let marcher = create_marcher(&march_parameters);
 
         Chunk { 
             quad: None,
@@ -159,13 +171,46 @@ impl Chunk {
         }
     }
 
-    pub fn sphere(sync_context: SynchronizationContext, programm_storage: ShaderStorage, debugger: Debugger, center: Vec3) -> Chunk {
-        let mut c = Chunk::uninitialized(sync_context, programm_storage, debugger);
+    pub fn sphere(
+        sync_context: SynchronizationContext, 
+        programm_storage: ShaderStorage, 
+        debugger: Debugger, 
+        num_of_cubes: IVec3, 
+        center: Vec3
+    ) -> Chunk {
+        let mut c = Chunk::uninitialized(sync_context, programm_storage, debugger, num_of_cubes);
         fill_sphere(&mut c, center);
         c.march();
         c
     }
 
+    pub fn from_field_data(
+        sync_context: SynchronizationContext, 
+        programm_storage: ShaderStorage, 
+        debugger: Debugger, 
+        num_of_cubes: IVec3,
+        surface_level: f32,
+        data: &[f32]
+    ) -> Chunk {
+        let mut c = Chunk::uninitialized(sync_context, programm_storage, debugger, num_of_cubes);
+        c.march_parameters.surface_level = surface_level;
+        c.set_field_data(data);
+        c
+    }
+
+    // chunk without any material, every texel is outside the surface
+    pub fn empty(
+        sync_context: SynchronizationContext, 
+        programm_storage: ShaderStorage, 
+        debugger: Debugger, 
+        num_of_cubes: IVec3,
+        surface_level: f32
+    ) -> Chunk {
+        let tex_dim = texture_dim(num_of_cubes);
+        let data = vec![1.; (tex_dim.x * tex_dim.y * tex_dim.z) as usize];
+        Chunk::from_field_data(sync_context, programm_storage, debugger, num_of_cubes, surface_level, &data)
+    }
+
     // pub fn empty(sync_context: SynchronizationContext, programm_storage: ShaderStorage) -> Chunk {
     //     let mut c = Chunk::uninitialized(sync_context, programm_storage);
     //     fill_empty(&mut c);
@@ -173,6 +218,89 @@ impl Chunk {
     //     c
     // }
 
+    pub fn surface_level(&self) -> f32 {
+        self.march_parameters.surface_level
+    }
+
+    pub fn texture_dim(&self) -> IVec3 {
+        texture_dim(self.march_parameters.num_of_cubes)
+    }
+
+    pub fn field_data(&mut self) -> Vec<f32> {
+        self.march_parameters.sync_context.force_sync(ShaderImageAccessBarrier | TextureUpdateBarrier);
+        self.march_parameters.distance_field.read_f32()
+    }
+
+    // replaces the whole distance field, the chunk has to be marched afterwards
+    pub fn set_field_data(&mut self, data: &[f32]) {
+        self.march_parameters.sync_context.force_sync(TextureUpdateBarrier);
+        self.march_parameters.distance_field.write_f32(data);
+        self.march_parameters.sync_context.dirty(ShaderImageAccessBarrier);
+
+        self.is_sdf_top_level_dirty = true;
+        self.march_parameters.dirty_area = Bounds::min_max(IVec3::ZERO, self.march_parameters.num_of_cubes);
+    }
+
+    pub fn read_field_region(&mut self, region_min: IVec3, size: IVec3) -> Vec<f32> {
+        self.march_parameters.sync_context.force_sync(ShaderImageAccessBarrier | TextureUpdateBarrier);
+        self.march_parameters.distance_field.read_region_f32(0, region_min, size)
+    }
+
+    pub fn write_field_region(&mut self, region: &FieldRegion) {
+        self.march_parameters.sync_context.force_sync(TextureUpdateBarrier);
+        self.march_parameters.distance_field.write_region_f32(0, region.min, region.size, &region.data);
+        self.march_parameters.sync_context.dirty(ShaderImageAccessBarrier);
+
+        let cubes_min = region.min - TEXTURE_OFFSET - IVec3::ONE;
+        let cubes_max = region.min + region.size - TEXTURE_OFFSET;
+        self.is_sdf_top_level_dirty = true;
+        self.march_parameters.dirty_area.encapsulate_other(&Bounds::min_max(
+            cubes_min.max(IVec3::ZERO), 
+            cubes_max.min(self.march_parameters.num_of_cubes)));
+    }
+
+    pub fn lod(&self) -> u32 {
+        self.march_parameters.lod
+    }
+
+    // reallocates mesh buffers for the new level, the chunk has to be marched afterwards
+    pub fn set_lod(&mut self, lod: u32) {
+        if lod == self.march_parameters.lod {
+            return;
+        }
+
+        self.march_parameters.sync_context.force_sync(ShaderStorageBarrier | CommandBarrier);
+        self.marcher.delete();
+        self.march_parameters.model_vertex_buffer.delete();
+
+        self.march_parameters.lod = lod;
+        self.march_parameters.model_vertex_buffer = model_vertex_buffer(self.march_parameters.lod_num_of_cubes());
+        self.marcher = create_marcher(&self.march_parameters);
+        self.march_parameters.collision_field.set_lod(lod);
+        self.march_parameters.dirty_area = Bounds::min_max(IVec3::ZERO, self.march_parameters.num_of_cubes);
+    }
+
+    // returns whether the faces changed, in which case the chunk has to be marched again
+    pub fn set_transition_faces(&mut self, faces: u8) -> bool {
+        let changed = self.march_parameters.transition_faces != faces;
+        self.march_parameters.transition_faces = faces;
+        changed
+    }
+
+    pub fn read_mesh(&mut self) -> (Vec<ModelVertex>, Vec<u32>) {
+        self.marcher.read_mesh(&mut self.march_parameters)
+    }
+
+    pub fn delete(mut self) {
+        self.marcher.delete();
+        self.march_parameters.distance_field.delete();
+        self.march_parameters.model_vertex_buffer.delete();
+        self.march_parameters.collision_field.delete();
+        if let Some(drawer) = &mut self.collider_drawer {
+            drawer.delete();
+        }
+    }
+
     pub fn march(&mut self) {
         self.before_march();
         for step in 0..self.march_steps_count() {
@@ -191,6 +319,11 @@ impl Chunk {
 
     pub fn before_march(&mut self) {
         self.march_parameters.sync_context.sync(ShaderImageAccessBarrier);
+        if self.march_parameters.lod > 0 && !self.march_parameters.dirty_area.is_empty() {
+            // coarse levels are marched from the mip chain, so it has to be up to date
+            self.march_parameters.sync_context.force_sync(TextureFetchBarrier | TextureUpdateBarrier);
+            self.actualise_texture();
+        }
     }
 
     pub fn after_march(&mut self) {
@@ -209,7 +342,7 @@ impl Chunk {
             model: *draw_parameters.model,
             view: draw_parameters.camera.view_matrix(),
             projection: draw_parameters.camera.projection_matrix(),
-            chunk_scale_factor: CHUNK_SCALE_FACTOR,
+            chunk_scale_factor: chunk_scale_factor(self.march_parameters.num_of_cubes),
             scalar_field: 1.into(),
             field_chunk_size_diff: TEXTURE_SIZE_DELTA,
             surface_level: self.march_parameters.surface_level,
@@ -238,7 +371,9 @@ impl Chunk {
 
         let drawer = self.collider_drawer.get_or_insert_with(|| {
             actualize = true;
-            CollisionShapeDebugView::new(self.march_parameters.programm_storage.clone())
+            CollisionShapeDebugView::new(
+                self.march_parameters.programm_storage.clone(), 
+                self.march_parameters.num_of_cubes)
         });
         if self.is_collision_shape_dirty {
             actualize = true;
@@ -257,7 +392,7 @@ impl Chunk {
     }
 
     // pub fn raycast(&mut self, ray: Ray, draw: impl Fn(Vec3, Vec3)) -> Option<Vec3> {
-    pub fn raycast(&mut self, ray: Ray) -> Option<Vec3> {
+    pub fn raycast(&mut self, ray: Ray) -> Option<RaycastHit> {
 
         if self.is_collision_shape_dirty {
             self.march_parameters.sync_context.sync(ShaderStorageBarrier);
@@ -267,8 +402,11 @@ impl Chunk {
         };
 
 
+        let collision_field = &self.march_parameters.collision_field;
+        let scale = collision_field.lod_scale() as f32;
+        let cubes = collision_field.num_of_cubes();
         for c in march_grid_by_ray(
-            ray.origin, ray.direction, IVec3::ZERO, NUM_OF_CUBES - IVec3::ONE)? {
+            ray.origin / scale, ray.direction, IVec3::ZERO, cubes - IVec3::ONE)? {
             // let center = (c.as_vec3() + Vec3::ONE * 0.5) * CHUNK_SCALE_FACTOR;
             // let size = CHUNK_SCALE_FACTOR;
             // draw(center, size);
@@ -278,10 +416,10 @@ impl Chunk {
             let triangles = self.march_parameters.collision_field.get(c);
 
             if triangles.len() > 0 {
-                self.debugger.draw_width(DebugPrimitive::Box { corner: c.as_vec3(), size: Vec3::ONE }, Color32::RED, 2.);
+                self.debugger.draw_width(DebugPrimitive::Box { corner: c.as_vec3() * scale, size: Vec3::ONE * scale }, Color32::RED, 2.);
             }
             else {
-                self.debugger.draw_width(DebugPrimitive::Box { corner: c.as_vec3(), size: Vec3::ONE }, Color32::RED, 0.1);
+                self.debugger.draw_width(DebugPrimitive::Box { corner: c.as_vec3() * scale, size: Vec3::ONE * scale }, Color32::RED, 0.1);
             }
 
             for triangle in triangles {
@@ -290,11 +428,14 @@ impl Chunk {
                 if debug_enabled {
                     self.debugger.draw(DebugPrimitive::Triangle(triangle.clone()), Color32::GOLD);
                 }
-                if intersection.is_some() {
+                if let Some(point) = intersection {
                     if debug_enabled {
                         self.debugger.draw_width(DebugPrimitive::Triangle(triangle.clone()), Color32::BLUE, 2.);
                     }
-                    return intersection;
+                    // collision triangles come from the same table as the marched mesh,
+                    // clockwise seen from outside, so the cross product points inward
+                    let normal = -triangle.normal();
+                    return Some(RaycastHit { point, normal });
                 }
             }
         }
@@ -314,10 +455,15 @@ impl Chunk {
         self.march_parameters.sync_context.sync(ShaderImageAccessBarrier);
     }
 
+    // measuring pass of a brush, the field is only read
+    pub fn gather_brush(&mut self, brush: &mut Brush) {
+        brush.gather(&mut self.march_parameters.distance_field);
+    }
+
     pub fn apply_brush(&mut self, brush: &mut Brush) {      
         
         
-        self.march_parameters.dirty_area.encapsulate_other(&brush.chunk_space_cords(NUM_OF_CUBES));
+        self.march_parameters.dirty_area.encapsulate_other(&brush.chunk_space_cords(self.march_parameters.num_of_cubes));
         // self.swap_buffer_is_actual = true;
         brush.apply(&mut self.march_parameters.distance_field);
         self.march_parameters.sync_context.dirty(ShaderImageAccessBarrier);
@@ -4,20 +4,24 @@ use std::{fmt::Debug, ops::Index};
 use egui_glfw_gl::gl;
 use glam::{ivec3, vec3, IVec3, Mat4, Vec3};
 
-use crate::{algorithms::{camera::Camera, transform::Transform, Triangle}, application::{app_logick::{ceil_div, NUM_OF_CUBES}, support::{shaders::{shaders_loader::ShaderStorage, ModelProgramm}, triangulation_table::triangulate_centers}}};
+use crate::{algorithms::{camera::Camera, transform::Transform, Triangle}, application::{app_logick::ceil_div, support::{shaders::{shaders_loader::ShaderStorage, ModelProgramm}, triangulation_table::triangulate_centers}}};
 
 use super::{DrawParameters, ModelVertex};
 
 
 pub const COMPRESS_COLLISION: bool = true;
 
-const NUM_OF_BLOCKS: usize = if !COMPRESS_COLLISION {
-    (NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z) as usize
-} else {
-    ceil_div((NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z) as usize, 4)
-};
+fn total_num_of_cubes(num_of_cubes: IVec3) -> usize {
+    (num_of_cubes.x * num_of_cubes.y * num_of_cubes.z) as usize
+}
 
-const TOTAL_NUM_OF_CUBES: usize = (NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z) as usize; 
+fn num_of_blocks(num_of_cubes: IVec3) -> usize {
+    if !COMPRESS_COLLISION {
+        total_num_of_cubes(num_of_cubes)
+    } else {
+        ceil_div(total_num_of_cubes(num_of_cubes), 4)
+    }
+}
 
 
 #[derive(Uniforms)]
@@ -60,32 +64,52 @@ impl Debug for BlockTriangles {
 
 #[derive(Debug)]
 pub struct CollisionShape {
-    raw_field: Box<[u32; NUM_OF_BLOCKS]>,
+    raw_field: Box<[u32]>,
     buffer: Buffer,
     triangles: Vec<BlockTriangles>,
-    update_iteration: u64
-}
-
-fn position(index: IVec3) -> usize {
-    (index.x + 
-        index.y * NUM_OF_CUBES.x + 
-        index.z * NUM_OF_CUBES.y * NUM_OF_CUBES.x) as usize
+    update_iteration: u64,
+    // full resolution cubes of the chunk
+    num_of_cubes: IVec3,
+    // cubes are marched at this lod, every cube covers 2^lod full resolution cubes
+    lod: u32,
 }
 
 impl CollisionShape {
-    pub fn new() -> CollisionShape {
-        let field = Box::new([0u32; NUM_OF_BLOCKS]);
+    pub fn new(num_of_cubes: IVec3) -> CollisionShape {
+        let field = vec![0u32; num_of_blocks(num_of_cubes)].into_boxed_slice();
         let buffer = Buffer::from_data(field.as_ref(), Usage::dynamic_read());
-        let triangles = vec![BlockTriangles::new(); TOTAL_NUM_OF_CUBES];
+        let triangles = vec![BlockTriangles::new(); total_num_of_cubes(num_of_cubes)];
 
         CollisionShape { 
             raw_field: field,
             update_iteration: 0, 
             buffer,
-            triangles
+            triangles,
+            num_of_cubes,
+            lod: 0,
         }
     }
 
+    pub fn lod_scale(&self) -> i32 {
+        1 << self.lod
+    }
+
+    pub fn num_of_cubes(&self) -> IVec3 {
+        self.num_of_cubes / self.lod_scale()
+    }
+
+    pub fn set_lod(&mut self, lod: u32) {
+        self.lod = lod;
+        self.update_iteration += 1;
+    }
+
+    fn position(&self, index: IVec3) -> usize {
+        let cubes = self.num_of_cubes();
+        (index.x + 
+            index.y * cubes.x + 
+            index.z * cubes.y * cubes.x) as usize
+    }
+
     pub fn readback(&mut self) {
         self.buffer.read_data_from_start(self.raw_field.as_mut());
         self.update_iteration += 1;
@@ -99,8 +123,12 @@ impl CollisionShape {
         &mut self.buffer
     }
 
+    pub fn delete(&mut self) {
+        self.buffer.delete();
+    }
+
     fn fill(&mut self, index: IVec3) {
-        let position = position(index);
+        let position = self.position(index);
 
         let config = if COMPRESS_COLLISION {
             let field_index = position / 4;
@@ -111,11 +139,17 @@ impl CollisionShape {
             self.raw_field[position]
         };
 
-        self.triangles[position].fill(config as u8, index.as_vec3() + Vec3::ONE * 0.5, Vec3::ONE, 0.01, self.update_iteration);
+        let scale = self.lod_scale() as f32;
+        self.triangles[position].fill(config as u8, 
+            (index.as_vec3() + Vec3::ONE * 0.5) * scale, 
+            Vec3::ONE * scale, 
+            0.01 * scale, 
+            self.update_iteration);
     }
 
+    // index is in cubes of the current lod
     pub fn get(&mut self, index: IVec3) -> &[Triangle] {
-        let position = position(index);
+        let position = self.position(index);
 
         if self.triangles[position].update_iteration != self.update_iteration {
             self.fill(index);
@@ -132,9 +166,9 @@ pub struct CollisionShapeDebugView {
 }
 
 impl CollisionShapeDebugView {
-    pub fn new(shader_storage: ShaderStorage) -> CollisionShapeDebugView {
+    pub fn new(shader_storage: ShaderStorage, num_of_cubes: IVec3) -> CollisionShapeDebugView {
         let buffer = VertexBuffer::empty(
-            (NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z * 15) as usize, Usage::dynamic_copy());
+            total_num_of_cubes(num_of_cubes) * 15, Usage::dynamic_copy());
         let vertex_count = 0;
 
         CollisionShapeDebugView { buffer, vertex_count, shader_storage }
@@ -144,9 +178,10 @@ impl CollisionShapeDebugView {
 
         let mut result_buffer = Vec::new();
 
-        for z in (0..NUM_OF_CUBES.z) {
-            for y in (0..NUM_OF_CUBES.y) {
-                for x in (0..NUM_OF_CUBES.x) {
+        let cubes = shape.num_of_cubes();
+        for z in (0..cubes.z) {
+            for y in (0..cubes.y) {
+                for x in (0..cubes.x) {
                     for triangle in shape.get(ivec3(x, y, z)) {
                         let normal = triangle.normal();
                         result_buffer.push(ModelVertex {
@@ -170,6 +205,10 @@ impl CollisionShapeDebugView {
         self.vertex_count = result_buffer.len() as i32;
     }
 
+    pub fn delete(&mut self) {
+        self.buffer.delete();
+    }
+
     pub fn draw(&mut self, params: DrawParameters<'_>) {
         
         self.buffer.bind();
@@ -0,0 +1,255 @@
+use std::{fs::File, io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write}, path::Path};
+
+use glam::IVec3;
+
+use super::field::CHUNK_RESOLUTIONS;
+
+const MAGIC: &[u8; 8] = b"BLOBFLD\0";
+pub const DOCUMENT_VERSION: u32 = 1;
+// apron around the chunk, the editor uses 8
+const MAX_TEXTURE_OFFSET: i32 = 64;
+
+pub struct ChunkData {
+    pub cord: IVec3,
+    pub field: Vec<f32>,
+}
+
+// on-disk layout (little endian):
+// magic, version: u32, num_of_cubes: ivec3, texture_offset: ivec3, surface_level: f32,
+// chunk_count: u32, then for every chunk: cord: ivec3, texture_dim^3 x f32
+pub struct FieldDocument {
+    pub num_of_cubes: IVec3,
+    pub texture_offset: IVec3,
+    pub surface_level: f32,
+    pub chunks: Vec<ChunkData>,
+}
+
+pub fn texture_dim(num_of_cubes: IVec3, texture_offset: IVec3) -> IVec3 {
+    num_of_cubes + texture_offset * 2 + IVec3::ONE
+}
+
+fn write_u32(w: &mut impl Write, val: u32) -> Result<(), String> {
+    w.write_all(&val.to_le_bytes()).map_err(|e| format!("{e}"))
+}
+
+fn write_f32(w: &mut impl Write, val: f32) -> Result<(), String> {
+    w.write_all(&val.to_le_bytes()).map_err(|e| format!("{e}"))
+}
+
+fn write_ivec3(w: &mut impl Write, val: IVec3) -> Result<(), String> {
+    for c in val.to_array() {
+        w.write_all(&c.to_le_bytes()).map_err(|e| format!("{e}"))?;
+    }
+    Ok(())
+}
+
+fn read_bytes<const N: usize>(r: &mut impl Read) -> Result<[u8; N], String> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf).map_err(|e| format!("{e}"))?;
+    Ok(buf)
+}
+
+fn read_u32(r: &mut impl Read) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(read_bytes(r)?))
+}
+
+fn read_f32(r: &mut impl Read) -> Result<f32, String> {
+    Ok(f32::from_le_bytes(read_bytes(r)?))
+}
+
+fn read_ivec3(r: &mut impl Read) -> Result<IVec3, String> {
+    let x = i32::from_le_bytes(read_bytes(r)?);
+    let y = i32::from_le_bytes(read_bytes(r)?);
+    let z = i32::from_le_bytes(read_bytes(r)?);
+    Ok(IVec3::new(x, y, z))
+}
+
+// texels of a chunk texture, none when the grid is invalid or too large to address
+fn checked_texel_count(num_of_cubes: IVec3, texture_offset: IVec3) -> Option<usize> {
+    let max_cubes = *CHUNK_RESOLUTIONS.iter().max()?;
+    if num_of_cubes.min_element() <= 0 || num_of_cubes.max_element() > max_cubes ||
+        texture_offset.min_element() < 0 || texture_offset.max_element() > MAX_TEXTURE_OFFSET {
+        return None;
+    }
+    let dim = texture_dim(num_of_cubes, texture_offset);
+    (dim.x as usize)
+        .checked_mul(dim.y as usize)?
+        .checked_mul(dim.z as usize)
+}
+
+impl FieldDocument {
+    pub fn texel_count(&self) -> usize {
+        let dim = texture_dim(self.num_of_cubes, self.texture_offset);
+        (dim.x * dim.y * dim.z) as usize
+    }
+
+    // checks everything the field needs before it drops its current chunks
+    pub fn validate(&self) -> Result<(), String> {
+        let texel_count = checked_texel_count(self.num_of_cubes, self.texture_offset)
+            .ok_or_else(|| format!("invalid chunk grid: {} cubes, {} offset", self.num_of_cubes, self.texture_offset))?;
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if chunk.field.len() != texel_count {
+                return Err(format!("chunk {} has {} texels, expected {}",
+                    chunk.cord, chunk.field.len(), texel_count));
+            }
+            if self.chunks[..i].iter().any(|c| c.cord == chunk.cord) {
+                return Err(format!("chunk {} is stored twice", chunk.cord));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("{e}"))?;
+        self.write_to(&mut BufWriter::new(file))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<FieldDocument, String> {
+        let file = File::open(path).map_err(|e| format!("{e}"))?;
+        FieldDocument::read_from(&mut BufReader::new(file))
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> Result<(), String> {
+        w.write_all(MAGIC).map_err(|e| format!("{e}"))?;
+        write_u32(w, DOCUMENT_VERSION)?;
+        write_ivec3(w, self.num_of_cubes)?;
+        write_ivec3(w, self.texture_offset)?;
+        write_f32(w, self.surface_level)?;
+        write_u32(w, self.chunks.len() as u32)?;
+
+        let texel_count = self.texel_count();
+        let mut bytes = Vec::with_capacity(texel_count * 4);
+        for chunk in &self.chunks {
+            if chunk.field.len() != texel_count {
+                return Err(format!("chunk {} has {} texels, expected {}",
+                    chunk.cord, chunk.field.len(), texel_count));
+            }
+            write_ivec3(w, chunk.cord)?;
+            bytes.clear();
+            for v in &chunk.field {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+            w.write_all(&bytes).map_err(|e| format!("{e}"))?;
+        }
+        w.flush().map_err(|e| format!("{e}"))
+    }
+
+    pub fn read_from<R: Read + Seek>(r: &mut R) -> Result<FieldDocument, String> {
+        let magic: [u8; 8] = read_bytes(r)?;
+        if &magic != MAGIC {
+            return Err("not a field document".to_owned());
+        }
+        let version = read_u32(r)?;
+        if version != DOCUMENT_VERSION {
+            return Err(format!("unsupported document version {version}"));
+        }
+
+        let num_of_cubes = read_ivec3(r)?;
+        let texture_offset = read_ivec3(r)?;
+        let texel_count = checked_texel_count(num_of_cubes, texture_offset)
+            .ok_or_else(|| format!("invalid chunk grid: {num_of_cubes} cubes, {texture_offset} offset"))?;
+        let surface_level = read_f32(r)?;
+        let chunk_count = read_u32(r)?;
+
+        // the header must not promise more chunks than the file holds
+        let chunk_bytes = texel_count as u64 * 4 + 12;
+        let position = r.stream_position().map_err(|e| format!("{e}"))?;
+        let end = r.seek(SeekFrom::End(0)).map_err(|e| format!("{e}"))?;
+        r.seek(SeekFrom::Start(position)).map_err(|e| format!("{e}"))?;
+        let available = end.saturating_sub(position);
+        if (chunk_count as u64).checked_mul(chunk_bytes).map_or(true, |needed| needed > available) {
+            return Err(format!("document is truncated: {chunk_count} chunks need more than {available} bytes"));
+        }
+
+        let mut document = FieldDocument { num_of_cubes, texture_offset, surface_level, chunks: Vec::new() };
+        let mut bytes = vec![0u8; texel_count * 4];
+
+        for _ in 0..chunk_count {
+            let cord = read_ivec3(r)?;
+            r.read_exact(&mut bytes).map_err(|e| format!("{e}"))?;
+            let field = bytes.chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            document.chunks.push(ChunkData { cord, field });
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn document() -> FieldDocument {
+        let num_of_cubes = IVec3::splat(16);
+        let texture_offset = IVec3::splat(8);
+        let dim = texture_dim(num_of_cubes, texture_offset);
+        let texel_count = (dim.x * dim.y * dim.z) as usize;
+        FieldDocument {
+            num_of_cubes,
+            texture_offset,
+            surface_level: 0.3,
+            chunks: vec![
+                ChunkData { cord: IVec3::ZERO, field: (0..texel_count).map(|i| i as f32 / texel_count as f32).collect() },
+                ChunkData { cord: IVec3::new(-1, 2, 0), field: vec![1.; texel_count] },
+            ],
+        }
+    }
+
+    fn write(document: &FieldDocument) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        document.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = document();
+        let read = FieldDocument::read_from(&mut Cursor::new(write(&original))).unwrap();
+
+        assert_eq!(read.num_of_cubes, original.num_of_cubes);
+        assert_eq!(read.texture_offset, original.texture_offset);
+        assert_eq!(read.surface_level, original.surface_level);
+        assert_eq!(read.chunks.len(), original.chunks.len());
+        for (a, b) in read.chunks.iter().zip(&original.chunks) {
+            assert_eq!(a.cord, b.cord);
+            assert_eq!(a.field, b.field);
+        }
+        assert!(read.validate().is_ok());
+    }
+
+    #[test]
+    fn truncated_document() {
+        let mut bytes = write(&document());
+        bytes.truncate(bytes.len() - 1);
+        assert!(FieldDocument::read_from(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn chunk_count_past_end_of_file() {
+        let mut bytes = write(&document());
+        // chunk_count follows magic, version, two ivec3 and surface_level
+        let chunk_count_at = 8 + 4 + 12 + 12 + 4;
+        bytes[chunk_count_at..chunk_count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(FieldDocument::read_from(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn huge_grid_in_header() {
+        let mut bytes = write(&document());
+        bytes[12..16].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert!(FieldDocument::read_from(&mut Cursor::new(bytes.clone())).is_err());
+
+        bytes[12..16].copy_from_slice(&0i32.to_le_bytes());
+        assert!(FieldDocument::read_from(&mut Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn not_a_document() {
+        assert!(FieldDocument::read_from(&mut Cursor::new(b"BLOBFLD".to_vec())).is_err());
+        assert!(FieldDocument::read_from(&mut Cursor::new(vec![0u8; 64])).is_err());
+    }
+}
@@ -2,73 +2,407 @@ use core::context::synchronization_context::{ShaderImageAccessBarrier, Synchroni
 use std::{collections::HashMap};
 
 use egui_glfw_gl::egui::Color32;
-use glam::{vec3, BVec3, IVec3, Mat4, Quat, Vec3};
+use glam::{BVec3, IVec3, Mat4, Quat, Vec3, Vec4Swizzles};
 
-use crate::{algorithms::{camera::Camera, cordinates::{to_vec3_const, RoundableToIVec3}, grid_line_intersection::march_grid_by_ray, raycast::Ray}, application::{app_logick::NUM_OF_CUBES, support::{bounds::{Bounds, Cord3D}, brush::Brush, debugger::{DebugPrimitive, Debugger}, shaders::shaders_loader::ShaderStorage}}};
+use crate::{algorithms::{camera::Camera, frustum::Frustum, cordinates::{to_vec3_const, RoundableToIVec3}, grid_line_intersection::march_grid_by_ray, raycast::{Ray, RaycastHit}}, application::{mesh_io::{voxelize::voxelize, Mesh, MeshNode, WELD_EPSILON}, support::{bounds::{Bounds, Cord3D}, brush::Brush, debugger::{DebugPrimitive, Debugger}, shaders::shaders_loader::ShaderStorage, symmetry::Symmetry}}};
 
-use super::{chunk::{self, Chunk, TEXTURE_OFFSET, TEXTURE_SIZE_DELTA, TEXTURE_SIZE_DELTA_HALVED}, DrawParameters};
+use super::{marching_cubes::{FACE_DIRECTIONS, MAX_LOD}, chunk::{self, texture_dim, Chunk, DEFAULT_SURFACE_LEVEL, TEXTURE_OFFSET, TEXTURE_SIZE_DELTA, TEXTURE_SIZE_DELTA_HALVED}, document::{ChunkData, FieldDocument}, history::{ChunkState, FieldRegion, History, HistoryEntry, DEFAULT_HISTORY_BUDGET}, DrawParameters};
 
 
 
 pub struct Field {
     chunks: HashMap<IVec3, Chunk>,
+    // cubes along every axis of a chunk, the same for all chunks of the field
+    num_of_cubes: IVec3,
     chunk_bounds: Bounds<IVec3>,
     sync_context: SynchronizationContext,
     shader_storage: ShaderStorage,
     debugger: Debugger,
+    history: History,
+    symmetry: Symmetry,
 }
 
 fn chunk_position(cord: IVec3) -> Vec3 {
     cord.as_vec3() * CHUNK_SIZE
 }
 
-fn chunk_matrix(cord: IVec3) -> Mat4 {
+fn chunk_matrix(cord: IVec3, num_of_cubes: IVec3) -> Mat4 {
     Mat4::from_scale_rotation_translation(
-        CHUNK_SCALE_FACTOR, 
+        chunk_scale_factor(num_of_cubes), 
         Quat::IDENTITY, 
         chunk_position(cord))
 }
 
-pub const CHUNK_SCALE_FACTOR: Vec3 = vec3(1. / (NUM_OF_CUBES.x as f32), 
-    1. / (NUM_OF_CUBES.y as f32),
-    1. / (NUM_OF_CUBES.z as f32));
+// size of a single cube in world space
+pub fn chunk_scale_factor(num_of_cubes: IVec3) -> Vec3 {
+    Vec3::ONE / num_of_cubes.as_vec3()
+}
+
+// chunks are a unit cube whatever their resolution
+const CHUNK_SIZE: Vec3 = Vec3::ONE;
 
-const CHUNK_SIZE: Vec3 = Vec3 {
-    x: CHUNK_SCALE_FACTOR.x * NUM_OF_CUBES.x as f32,
-    y: CHUNK_SCALE_FACTOR.y * NUM_OF_CUBES.y as f32,
-    z: CHUNK_SCALE_FACTOR.z * NUM_OF_CUBES.z as f32,
-};
+// cubes along every axis of a chunk, powers of two keep more mip levels for lod
+pub const CHUNK_RESOLUTIONS: [i32; 7] = [16, 24, 32, 48, 64, 96, 128];
 
 const MARCH_BY_STEPS: bool = true;
 
+// how far past a level boundary the screen size has to go before a chunk switches lod
+const LOD_HYSTERESIS: f32 = 0.3;
+const MAX_LOD_SWAPS_PER_FRAME: usize = 4;
+
 
 
 impl Field {
-    pub fn new(sync_context: SynchronizationContext, shader_storage: ShaderStorage, debugger: Debugger) -> Field {
+    pub fn new(
+        sync_context: SynchronizationContext, 
+        shader_storage: ShaderStorage, 
+        debugger: Debugger, 
+        num_of_cubes: IVec3
+    ) -> Field {
         let chunks = HashMap::new();
 
         let mut f = Field { 
             debugger,
             chunks, 
+            num_of_cubes,
             sync_context, 
             shader_storage, 
-            chunk_bounds: Bounds::empty() };
+            chunk_bounds: Bounds::empty(),
+            history: History::new(DEFAULT_HISTORY_BUDGET),
+            symmetry: Symmetry::default() };
 
         f.insert_chunk_at(IVec3::ZERO);
         f
     }
 
-    fn insert_chunk_at(&mut self, cord: IVec3) {
+    pub fn num_of_cubes(&self) -> IVec3 {
+        self.num_of_cubes
+    }
+
+    fn texture_dim(&self) -> IVec3 {
+        texture_dim(self.num_of_cubes)
+    }
+
+    // drops the sculpt and starts over from a single chunk with the given resolution
+    pub fn reset(&mut self, num_of_cubes: IVec3) {
+        self.clear();
+        self.num_of_cubes = num_of_cubes;
+        self.insert_chunk_at(IVec3::ZERO);
+    }
+
+    pub fn insert_chunk_at(&mut self, cord: IVec3) {
         let c = Chunk::sphere(
             self.sync_context.clone(), 
             self.shader_storage.clone(), 
             // self.debugger.clone(),
-            self.debugger.clone_with_matrix(chunk_matrix(cord)),
+            self.debugger.clone_with_matrix(chunk_matrix(cord, self.num_of_cubes)),
+            self.num_of_cubes,
             -cord.as_vec3() + Vec3::ONE * 0.5);
-        self.chunks.insert(cord, c);
+        self.insert_chunk(cord, c);
+    }
+
+    fn insert_empty_chunk_at(&mut self, cord: IVec3, surface_level: f32) {
+        let c = Chunk::empty(
+            self.sync_context.clone(), 
+            self.shader_storage.clone(), 
+            self.debugger.clone_with_matrix(chunk_matrix(cord, self.num_of_cubes)),
+            self.num_of_cubes,
+            surface_level);
+        self.insert_chunk(cord, c);
+    }
+
+    fn insert_chunk(&mut self, cord: IVec3, chunk: Chunk) {
+        if let Some(old) = self.chunks.insert(cord, chunk) {
+            old.delete();
+        }
         self.chunk_bounds.encapsulate(cord);
     }
 
+    fn remove_chunk(&mut self, cord: IVec3) {
+        if let Some(chunk) = self.chunks.remove(&cord) {
+            chunk.delete();
+        }
+        self.chunk_bounds = Bounds::empty();
+        for cord in self.chunks.keys() {
+            self.chunk_bounds.encapsulate(*cord);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for (_, chunk) in self.chunks.drain() {
+            chunk.delete();
+        }
+        self.chunk_bounds = Bounds::empty();
+        self.history.clear();
+    }
+
+    pub fn symmetry(&self) -> &Symmetry {
+        &self.symmetry
+    }
+
+    pub fn symmetry_mut(&mut self) -> &mut Symmetry {
+        &mut self.symmetry
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.history.begin_stroke();
+    }
+
+    pub fn end_stroke(&mut self) {
+        self.history.end_stroke(self.texture_dim());
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(mut entry) = self.history.take_undo() else {
+            return false;
+        };
+        self.restore(&mut entry);
+        self.history.push_redo(entry);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(mut entry) = self.history.take_redo() else {
+            return false;
+        };
+        self.restore(&mut entry);
+        self.history.push_undo(entry);
+        true
+    }
+
+    // writes the entry into the field and stores the overwritten state in its place
+    fn restore(&mut self, entry: &mut HistoryEntry) {
+        let surface_level = self.surface_level();
+        let tex_dim = self.texture_dim();
+        let mut cords = Vec::new();
+
+        for (cord, state) in entry.chunks.iter_mut() {
+            let current = match (self.chunks.get_mut(cord), &*state) {
+                (None, _) => ChunkState::Missing,
+                (Some(chunk), ChunkState::Missing) => 
+                    ChunkState::Field(FieldRegion::full(chunk.field_data(), tex_dim)),
+                (Some(chunk), ChunkState::Field(region)) => ChunkState::Field(FieldRegion { 
+                    min: region.min, 
+                    size: region.size, 
+                    data: chunk.read_field_region(region.min, region.size) 
+                }),
+            };
+
+            match std::mem::replace(state, current) {
+                ChunkState::Missing => self.remove_chunk(*cord),
+                ChunkState::Field(region) => {
+                    // the region may cover only part of the chunk, a missing chunk starts out empty
+                    if !self.chunks.contains_key(cord) {
+                        self.insert_empty_chunk_at(*cord, surface_level);
+                    }
+                    self.chunks.get_mut(cord).unwrap().write_field_region(&region);
+                    cords.push(*cord);
+                }
+            }
+        }
+
+        self.march_chunks(&cords);
+    }
+
+    fn surface_level(&self) -> f32 {
+        self.chunks.values().next()
+            .map(|c| c.surface_level())
+            .unwrap_or(DEFAULT_SURFACE_LEVEL)
+    }
+
+    pub fn to_document(&mut self) -> FieldDocument {
+        let surface_level = self.surface_level();
+
+        let mut cords: Vec<_> = self.chunks.keys().copied().collect();
+        cords.sort_by_key(|c| (c.z, c.y, c.x));
+
+        let chunks = cords.into_iter().map(|cord| ChunkData {
+            cord,
+            field: self.chunks.get_mut(&cord).unwrap().field_data()
+        }).collect();
+
+        FieldDocument { 
+            num_of_cubes: self.num_of_cubes, 
+            texture_offset: TEXTURE_OFFSET, 
+            surface_level, 
+            chunks 
+        }
+    }
+
+    pub fn load_document(&mut self, document: &FieldDocument) -> Result<(), String> {
+        if document.texture_offset != TEXTURE_OFFSET {
+            return Err(format!(
+                "document texture offset {} does not match editor offset {}",
+                document.texture_offset, TEXTURE_OFFSET));
+        }
+        document.validate()?;
+
+        self.clear();
+        self.num_of_cubes = document.num_of_cubes;
+
+        for chunk in &document.chunks {
+            let c = Chunk::from_field_data(
+                self.sync_context.clone(), 
+                self.shader_storage.clone(), 
+                self.debugger.clone_with_matrix(chunk_matrix(chunk.cord, self.num_of_cubes)),
+                self.num_of_cubes,
+                document.surface_level,
+                &chunk.field);
+            self.insert_chunk(chunk.cord, c);
+        }
+
+        let cords: Vec<_> = self.chunks.keys().copied().collect();
+        self.march_chunks(&cords);
+        Ok(())
+    }
+
+    pub fn collect_mesh(&mut self) -> Mesh {
+        self.set_full_detail();
+        let mut cords: Vec<_> = self.chunks.keys().copied().collect();
+        cords.sort_by_key(|c| (c.z, c.y, c.x));
+
+        let mut mesh = Mesh::default();
+        for cord in cords {
+            let (vertices, indices) = self.chunks.get_mut(&cord).unwrap().read_mesh();
+            mesh.append_marched(&vertices, &indices, chunk_matrix(cord, self.num_of_cubes));
+        }
+        mesh.welded(WELD_EPSILON)
+    }
+
+    // mesh is given in world space and is merged into the field as a union, 
+    // when replacing, the field is cleared first. returns the number of updated chunks
+    pub fn import_mesh(&mut self, mesh: &Mesh, replace: bool) -> usize {
+        // replacing drops the history with the chunks, otherwise the import is a single undo step
+        if replace {
+            self.clear();
+        } else {
+            self.history.begin_stroke();
+        }
+
+        let surface_level = self.surface_level();
+        let num_of_cubes = self.num_of_cubes;
+        let tex_dim = self.texture_dim();
+        // matches the slope of the field written by fill_sphere
+        let value_per_texel = 2. / (tex_dim.x - 1) as f32;
+        let margin = ((1. - surface_level) / value_per_texel).ceil() as i32 + 1;
+
+        let lattice: Vec<Vec3> = mesh.positions.iter()
+            .map(|p| *p * num_of_cubes.as_vec3())
+            .collect();
+        let grid = voxelize(&lattice, &mesh.indices, margin);
+
+        // chunks whose cubes can hold the surface are created, chunks that only 
+        // overlap the grid with their texture border are updated when they exist
+        let surface_min = grid.origin + IVec3::splat(margin);
+        let surface_max = grid.max() - IVec3::splat(margin);
+        let surface_chunks = Bounds::min_max(
+            (surface_min + num_of_cubes - IVec3::ONE).div_euclid(num_of_cubes) - IVec3::ONE,
+            surface_max.div_euclid(num_of_cubes));
+        let touched_chunks = Bounds::min_max(
+            (grid.origin - TEXTURE_OFFSET - IVec3::ONE).div_euclid(num_of_cubes),
+            (grid.max() + TEXTURE_OFFSET).div_euclid(num_of_cubes));
+
+        let mut cords = Vec::new();
+        for cord in touched_chunks.iterate_cords() {
+            let created = !self.chunks.contains_key(&cord);
+            if created {
+                if !surface_chunks.contains(cord) {
+                    continue;
+                }
+                self.history.record_created(cord);
+                self.insert_empty_chunk_at(cord, surface_level);
+            }
+
+            let first_texel = cord * num_of_cubes - TEXTURE_OFFSET;
+            let chunk = self.chunks.get_mut(&cord).unwrap();
+            let chunk_surface = chunk.surface_level();
+            let mut data = if created {
+                vec![1.; (tex_dim.x * tex_dim.y * tex_dim.z) as usize]
+            } else {
+                let data = chunk.field_data();
+                let region = Bounds::min_max(
+                    (grid.origin - first_texel).max(IVec3::ZERO),
+                    (grid.max() - first_texel).min(tex_dim - IVec3::ONE));
+                self.history.record_existing(cord, tex_dim);
+                self.history.capture(cord, tex_dim, &FieldRegion::crop(&data, tex_dim, region));
+                self.history.touch(cord, region);
+                data
+            };
+
+            let mut index = 0;
+            for z in 0..tex_dim.z {
+                for y in 0..tex_dim.y {
+                    for x in 0..tex_dim.x {
+                        if let Some(d) = grid.sample(first_texel + IVec3::new(x, y, z)) {
+                            let value = (chunk_surface + d * value_per_texel).clamp(0., 1.);
+                            data[index] = data[index].min(value);
+                        }
+                        index += 1;
+                    }
+                }
+            }
+
+            chunk.set_field_data(&data);
+            cords.push(cord);
+        }
+
+        self.march_chunks(&cords);
+        self.history.end_stroke(tex_dim);
+        cords.len()
+    }
+
+    // chunk meshes in chunk cube space, positioned by chunk_matrix
+    pub fn collect_chunk_meshes(&mut self) -> Vec<MeshNode> {
+        self.set_full_detail();
+        let mut cords: Vec<_> = self.chunks.keys().copied().collect();
+        cords.sort_by_key(|c| (c.z, c.y, c.x));
+        let scale = chunk_scale_factor(self.num_of_cubes);
+
+        cords.into_iter().map(|cord| {
+            let (vertices, indices) = self.chunks.get_mut(&cord).unwrap().read_mesh();
+            let mut mesh = Mesh::default();
+            mesh.append_marched(&vertices, &indices, Mat4::IDENTITY);
+            MeshNode { 
+                name: format!("chunk_{}_{}_{}", cord.x, cord.y, cord.z), 
+                mesh: mesh.welded(WELD_EPSILON), 
+                translation: chunk_position(cord), 
+                scale 
+            }
+        }).collect()
+    }
+
+    fn march_chunks(&mut self, cords: &[IVec3]) {
+        let mut max_march_steps = 0;
+        for cord in cords {
+            let chunk = self.chunks.get_mut(cord).unwrap();
+            chunk.before_march();
+            max_march_steps = usize::max(max_march_steps, chunk.march_steps_count());
+        }
+
+        for step in 0..max_march_steps {
+            for cord in cords {
+                let chunk = self.chunks.get_mut(cord).unwrap();
+                if step >= chunk.march_steps_count() {
+                    continue;
+                }
+                chunk.march_step(step);
+            }
+        }
+
+        for cord in cords {
+            let chunk = self.chunks.get_mut(cord).unwrap();
+            chunk.after_march();
+        }
+    }
+
     pub fn draw_distance_field(&mut self, camera: &impl Camera, slice: f32, parity: BVec3) {
         for (cord, chunk) in self.chunks.iter_mut() {
 
@@ -84,13 +418,14 @@ impl Field {
             //     size: CHUNK_SIZE + 3. * CHUNK_SCALE_FACTOR 
             // }, Color32::YELLOW);
 
+            let scale_factor = chunk_scale_factor(self.num_of_cubes);
             let position = chunk_position - 
-                TEXTURE_SIZE_DELTA_HALVED.x * CHUNK_SCALE_FACTOR.x * Vec3::X - 
-                TEXTURE_SIZE_DELTA_HALVED.y * CHUNK_SCALE_FACTOR.y * Vec3::Y;
-            let offset = -TEXTURE_SIZE_DELTA_HALVED.z * CHUNK_SCALE_FACTOR.z + 
-                (1. + TEXTURE_SIZE_DELTA.as_vec3().z * CHUNK_SCALE_FACTOR.z) * slice;
+                TEXTURE_SIZE_DELTA_HALVED.x * scale_factor.x * Vec3::X - 
+                TEXTURE_SIZE_DELTA_HALVED.y * scale_factor.y * Vec3::Y;
+            let offset = -TEXTURE_SIZE_DELTA_HALVED.z * scale_factor.z + 
+                (1. + TEXTURE_SIZE_DELTA.as_vec3().z * scale_factor.z) * slice;
             let model = Mat4::from_scale_rotation_translation(
-                Vec3::ONE + TEXTURE_SIZE_DELTA.as_vec3() * CHUNK_SCALE_FACTOR, 
+                Vec3::ONE + TEXTURE_SIZE_DELTA.as_vec3() * scale_factor, 
                 Quat::IDENTITY, 
                 position + Vec3::Z * offset);
             chunk.draw_distance_field(DrawParameters { camera: camera, model: &model }, slice);
@@ -98,7 +433,7 @@ impl Field {
     }
 
 
-    pub fn raycast(&mut self, ray: Ray) -> Option<Vec3> {
+    pub fn raycast(&mut self, ray: Ray) -> Option<RaycastHit> {
         
         for chunk_cord in march_grid_by_ray(
                 ray.origin / CHUNK_SIZE, 
@@ -114,29 +449,126 @@ impl Field {
             // self.debugger.draw(DebugPrimitive::Box { corner: chunk_position, size: CHUNK_SIZE }, Color32::GREEN);
             // self.debugger.draw(DebugPrimitive::Box { corner: chunk_position + CHUNK_SIZE * 0.25, size: CHUNK_SIZE * 0.5 }, Color32::YELLOW);
 
-            let adjusted_ray = Ray::new((ray.origin - chunk_position) / CHUNK_SCALE_FACTOR, ray.direction / CHUNK_SCALE_FACTOR);
+            let scale_factor = chunk_scale_factor(self.num_of_cubes);
+            let adjusted_ray = Ray::new((ray.origin - chunk_position) / scale_factor, ray.direction / scale_factor);
 
             if let Some(hit) = chunk.unwrap().raycast(adjusted_ray) {
-                return Some(hit * CHUNK_SCALE_FACTOR + chunk_position);
+                return Some(RaycastHit {
+                    point: hit.point * scale_factor + chunk_position,
+                    normal: (hit.normal / scale_factor).normalize_or_zero(),
+                });
             }
         };
         None
     }
 
+    // picks the coarsest lod at which a cube still covers at least min_pixels_per_cube pixels on screen.
+    // only visible chunks are updated and only a few of them per call, closest first
+    pub fn update_lods(&mut self, camera: &impl Camera, viewport_height: f32, min_pixels_per_cube: Option<f32>) {
+        let frustum = Frustum::from_matrix(camera.full_matrix());
+        let camera_position = camera.view_matrix().inverse().w_axis.xyz();
+        let focal = camera.projection_matrix().y_axis.y * viewport_height * 0.5;
+        let max_lod = (MAX_LOD as i32).min(self.num_of_cubes.min_element().trailing_zeros() as i32);
+        let scale_factor = chunk_scale_factor(self.num_of_cubes);
+
+        let mut swaps: Vec<(f32, IVec3, u32)> = Vec::new();
+        for (cord, chunk) in self.chunks.iter() {
+            let corner = chunk_position(*cord);
+            if !frustum.intersects_box(corner, CHUNK_SIZE) {
+                continue;
+            }
+
+            let closest = camera_position.clamp(corner, corner + CHUNK_SIZE);
+            let distance = camera_position.distance(closest).max(0.001);
+            let current = chunk.lod() as i32;
+
+            let new_lod = match min_pixels_per_cube {
+                None => 0,
+                Some(min_pixels) => {
+                    let pixels_per_cube = focal * scale_factor.max_element() / distance;
+                    let level = (min_pixels / pixels_per_cube).max(f32::MIN_POSITIVE).log2();
+                    if level >= (current + 1) as f32 + LOD_HYSTERESIS {
+                        (level - LOD_HYSTERESIS).floor() as i32
+                    }
+                    else if level < current as f32 - LOD_HYSTERESIS {
+                        (level + LOD_HYSTERESIS).floor() as i32
+                    }
+                    else {
+                        current
+                    }
+                }
+            }.clamp(0, max_lod);
+
+            if new_lod != current {
+                swaps.push((distance, *cord, new_lod as u32));
+            }
+        }
+
+        swaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+        swaps.truncate(MAX_LOD_SWAPS_PER_FRAME);
+
+        let mut cords: Vec<_> = swaps.iter().map(|(_, cord, lod)| {
+            self.chunks.get_mut(cord).unwrap().set_lod(*lod);
+            *cord
+        }).collect();
+        self.update_transition_faces(&mut cords);
+        self.march_chunks(&cords);
+    }
+
+    // marks faces shared with a neighbour of another lod, so their seams get capped,
+    // chunks whose faces changed are added to cords
+    fn update_transition_faces(&mut self, cords: &mut Vec<IVec3>) {
+        let faces: Vec<(IVec3, u8)> = self.chunks.iter().map(|(cord, chunk)| {
+            let mut faces = 0;
+            for (i, direction) in FACE_DIRECTIONS.iter().enumerate() {
+                if let Some(neighbour) = self.chunks.get(&(*cord + *direction)) {
+                    if neighbour.lod() != chunk.lod() {
+                        faces |= 1 << i;
+                    }
+                }
+            }
+            (*cord, faces)
+        }).collect();
+
+        for (cord, faces) in faces {
+            if self.chunks.get_mut(&cord).unwrap().set_transition_faces(faces) && !cords.contains(&cord) {
+                cords.push(cord);
+            }
+        }
+    }
+
+    // exports and other readbacks want the full resolution mesh
+    fn set_full_detail(&mut self) {
+        let mut cords: Vec<_> = self.chunks.iter()
+            .filter(|(_, c)| c.lod() != 0)
+            .map(|(cord, _)| *cord)
+            .collect();
+        for cord in &cords {
+            self.chunks.get_mut(cord).unwrap().set_lod(0);
+        }
+        self.update_transition_faces(&mut cords);
+        self.march_chunks(&cords);
+    }
+
     pub fn draw(&mut self, camera: &impl Camera) {
+        let frustum = Frustum::from_matrix(camera.full_matrix());
+
         for (cord, chunk) in self.chunks.iter_mut() {
 
             
             let chunk_position = chunk_position(*cord);
+            if !frustum.intersects_box(chunk_position, CHUNK_SIZE) {
+                continue;
+            }
             self.debugger.draw(DebugPrimitive::Box { corner: chunk_position, size: CHUNK_SIZE }, Color32::GREEN);
             // self.debugger.draw(DebugPrimitive::Box { 
             //     corner: chunk_position - 1.5 * CHUNK_SCALE_FACTOR, 
             //     size: CHUNK_SIZE + 3. * CHUNK_SCALE_FACTOR 
             // }, Color32::YELLOW);
-            let model = chunk_matrix(*cord);
+            let model = chunk_matrix(*cord, self.num_of_cubes);
                 
                 chunk.draw(DrawParameters { camera: camera, model: &model }, 
-                    TEXTURE_OFFSET.x as f32 * CHUNK_SCALE_FACTOR.x);
+                    TEXTURE_OFFSET.x as f32 * chunk_scale_factor(self.num_of_cubes).x);
         }
     }
 
@@ -147,81 +579,122 @@ impl Field {
             //     corner: chunk_position - 1.5 * CHUNK_SCALE_FACTOR, 
             //     size: CHUNK_SIZE + 3. * CHUNK_SCALE_FACTOR 
             // }, Color32::YELLOW);
-            let model = chunk_matrix(*cord);
+            let model = chunk_matrix(*cord, self.num_of_cubes);
                 
                 chunk.draw_debug_vew(DrawParameters { camera: camera, model: &model })
         }
     }
 
-    pub fn apply_brush(&mut self, brush: &Brush) {
+    // keeps the texels of the region as they were before the current stroke touched them,
+    // only the part not captured earlier in the stroke is read back
+    fn record_for_history(&mut self, cord: IVec3, region: Bounds<IVec3>) {
+        if !self.history.is_recording() {
+            return;
+        }
+        let tex_dim = self.texture_dim();
+        self.history.record_existing(cord, tex_dim);
+        let missing = self.history.uncaptured(cord, tex_dim, region);
+        if let (Some(missing), Some(chunk)) = (missing, self.chunks.get_mut(&cord)) {
+            let min = missing.min();
+            let size = missing.max() - min + IVec3::ONE;
+            let data = chunk.read_field_region(min, size);
+            self.history.capture(cord, tex_dim, &FieldRegion { min, size, data });
+        }
+        self.history.touch(cord, region);
+    }
 
+    // chunks whose texture, border included, the brush can reach
+    fn brush_chunk_bounds(&self, brush: &Brush) -> Bounds<IVec3> {
         let bounds = brush.bounds();
+        let scale_factor = chunk_scale_factor(self.num_of_cubes);
+        Bounds::min_max(
+            ((bounds.min() - TEXTURE_SIZE_DELTA_HALVED * scale_factor) / CHUNK_SIZE).floor_to_ivec(),
+            ((bounds.max() + TEXTURE_SIZE_DELTA_HALVED * scale_factor) / CHUNK_SIZE).floor_to_ivec()
+        )
+    }
+
+    // brushes that measure the surface do it over every chunk the dab reaches before any of them changes,
+    // so neighbouring chunks work with the same measurement
+    fn gather_brush(&mut self, brush: &Brush, chunk_bounds: &Bounds<IVec3>) {
+        if !brush.gathers() {
+            return;
+        }
+        brush.begin_gather();
+        for cord in chunk_bounds.iterate_cords() {
+            if let Some(chunk) = self.chunks.get_mut(&cord) {
+                let mut chunk_local_brush = brush.transformed(-chunk_position(cord), Vec3::ONE);
+                chunk.gather_brush(&mut chunk_local_brush);
+            }
+        }
+    }
 
+    // applies the brushes and their symmetric copies, chunks touched by any of them are marched together
+    pub fn apply_brushes(&mut self, brushes: &[Brush]) {
 
-        // self.debugger.draw(DebugPrimitive::Bounds(&bounds), Color32::RED);
+        // self.debugger.draw(DebugPrimitive::Bounds(&brush.bounds()), Color32::RED);
 
-        let chunk_bounds = Bounds::min_max(
-            ((bounds.min() - TEXTURE_SIZE_DELTA_HALVED * CHUNK_SCALE_FACTOR) / CHUNK_SIZE).floor_to_ivec(),
-            ((bounds.max() + TEXTURE_SIZE_DELTA_HALVED * CHUNK_SCALE_FACTOR) / CHUNK_SIZE).floor_to_ivec()
-        );
+        let tex_dim = self.texture_dim();
+        let brushes: Vec<_> = brushes.iter()
+            .flat_map(|b| self.symmetry.replicate(b))
+            .map(|b| {
+                let chunk_bounds = self.brush_chunk_bounds(&b);
+                (b, chunk_bounds)
+            })
+            .collect();
 
         if MARCH_BY_STEPS {
-            let cords: Vec<_> = chunk_bounds.iterate_cords().collect();
+            let mut cords: Vec<IVec3> = Vec::new();
+            for (_, chunk_bounds) in &brushes {
+                for cord in chunk_bounds.iterate_cords() {
+                    if !cords.contains(&cord) {
+                        cords.push(cord);
+                    }
+                }
+            }
     
             for cord in &cords {
                 if !self.chunks.contains_key(&cord) {
+                    self.history.record_created(*cord);
                     self.insert_chunk_at(*cord);
                     // dbg!("insert");
                 }
             }
     
     
-            for cord in &cords {
-                let chunk = self.chunks.get_mut(cord).unwrap();
-                let chunk_pos = chunk_position(*cord);
-                let mut chunk_local_brush = brush.transformed(-chunk_pos, Vec3::ONE);
-                chunk.apply_brush(&mut chunk_local_brush)
-            }
-    
-            let mut max_march_steps = 0;
-            for cord in &cords {
-                let chunk = self.chunks.get_mut(cord).unwrap();
-                chunk.before_march();
-                max_march_steps = usize::max(max_march_steps, chunk.march_steps_count());
-            }
-    
-            for step in 0..max_march_steps {
-                for cord in &cords {
-                    let chunk = self.chunks.get_mut(cord).unwrap();
-                    if step >= chunk.march_steps_count() {
-                        continue;
-                    }
-                    chunk.march_step(step);
+            for (brush, chunk_bounds) in &brushes {
+                self.gather_brush(brush, chunk_bounds);
+                for cord in chunk_bounds.iterate_cords() {
+                    let mut chunk_local_brush = brush.transformed(-chunk_position(cord), Vec3::ONE);
+                    self.record_for_history(cord, chunk_local_brush.texture_region(tex_dim));
+                    self.chunks.get_mut(&cord).unwrap().apply_brush(&mut chunk_local_brush)
                 }
             }
-    
-            for cord in &cords {
-                let chunk = self.chunks.get_mut(cord).unwrap();
-                chunk.after_march();
-            }
+
+            self.march_chunks(&cords);
         }
         else {
-            for cord in chunk_bounds.iterate_cords() {
-    
-                // dbg!(cord);
-    
-                if !self.chunks.contains_key(&cord) {
-                    self.insert_chunk_at(cord);
-                    // dbg!("insert");
+            for (brush, chunk_bounds) in &brushes {
+                for cord in chunk_bounds.iterate_cords() {
+                    if !self.chunks.contains_key(&cord) {
+                        self.history.record_created(cord);
+                        self.insert_chunk_at(cord);
+                    }
                 }
-    
-                if let Some(chunk) = self.chunks.get_mut(&cord) {
+                self.gather_brush(brush, chunk_bounds);
+
+                for cord in chunk_bounds.iterate_cords() {
+        
+                    // dbg!(cord);
+        
                     let chunk_pos = chunk_position(cord);
                     let mut chunk_local_brush = brush.transformed(-chunk_pos, Vec3::ONE);
-                    chunk.before_brush();
-                    chunk.apply_brush(&mut chunk_local_brush);
-    
-                    chunk.march();
+                    self.record_for_history(cord, chunk_local_brush.texture_region(tex_dim));
+                    if let Some(chunk) = self.chunks.get_mut(&cord) {
+                        chunk.before_brush();
+                        chunk.apply_brush(&mut chunk_local_brush);
+        
+                        chunk.march();
+                    }
                 }
             }
         }
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, VecDeque};
+
+use glam::IVec3;
+
+use crate::application::support::bounds::Bounds;
+
+pub const DEFAULT_HISTORY_BUDGET: usize = 256 * 1024 * 1024;
+
+// box of texels of a single chunk distance field
+pub struct FieldRegion {
+    pub min: IVec3,
+    pub size: IVec3,
+    pub data: Vec<f32>,
+}
+
+impl FieldRegion {
+    pub fn full(data: Vec<f32>, texture_dim: IVec3) -> FieldRegion {
+        FieldRegion { min: IVec3::ZERO, size: texture_dim, data }
+    }
+
+    // copies inclusive texel bounds out of the whole texture
+    pub fn crop(full: &[f32], texture_dim: IVec3, bounds: Bounds<IVec3>) -> FieldRegion {
+        let min = bounds.min();
+        let size = bounds.max() - min + IVec3::ONE;
+        let mut data = Vec::with_capacity((size.x * size.y * size.z) as usize);
+        for z in min.z..min.z + size.z {
+            for y in min.y..min.y + size.y {
+                let row = (texture_dim.x * (y + texture_dim.y * z) + min.x) as usize;
+                data.extend_from_slice(&full[row..row + size.x as usize]);
+            }
+        }
+        FieldRegion { min, size, data }
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.data.len() * std::mem::size_of::<f32>()
+    }
+}
+
+pub enum ChunkState {
+    Missing,
+    Field(FieldRegion),
+}
+
+impl ChunkState {
+    fn byte_size(&self) -> usize {
+        match self {
+            ChunkState::Missing => 0,
+            ChunkState::Field(region) => region.byte_size(),
+        }
+    }
+}
+
+// state of the chunks to restore. restoring swaps it with the current state, 
+// so the same entry moves back and forth between undo and redo stacks
+pub struct HistoryEntry {
+    pub chunks: Vec<(IVec3, ChunkState)>,
+}
+
+impl HistoryEntry {
+    pub fn byte_size(&self) -> usize {
+        self.chunks.iter().map(|(_, s)| s.byte_size()).sum()
+    }
+}
+
+// texels of a chunk read back so far during a stroke, each one as it was before the stroke first reached it
+struct CapturedField {
+    data: Vec<f32>,
+    captured: Vec<bool>,
+}
+
+impl CapturedField {
+    fn new(texture_dim: IVec3) -> CapturedField {
+        let count = (texture_dim.x * texture_dim.y * texture_dim.z) as usize;
+        CapturedField { data: vec![0.; count], captured: vec![false; count] }
+    }
+}
+
+struct StrokeChunk {
+    // None when the chunk was created by the stroke
+    before: Option<CapturedField>,
+    touched: Bounds<IVec3>,
+}
+
+pub struct History {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    stroke: Option<HashMap<IVec3, StrokeChunk>>,
+    budget: usize,
+}
+
+impl History {
+    pub fn new(budget: usize) -> History {
+        History { undo: VecDeque::new(), redo: Vec::new(), stroke: None, budget }
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.stroke = Some(HashMap::new());
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    pub fn record_created(&mut self, cord: IVec3) {
+        if let Some(stroke) = &mut self.stroke {
+            stroke.insert(cord, StrokeChunk { before: None, touched: Bounds::empty() });
+        }
+    }
+
+    pub fn record_existing(&mut self, cord: IVec3, texture_dim: IVec3) {
+        if let Some(stroke) = &mut self.stroke {
+            stroke.entry(cord).or_insert_with(|| StrokeChunk { 
+                before: Some(CapturedField::new(texture_dim)), 
+                touched: Bounds::empty() 
+            });
+        }
+    }
+
+    // smallest box holding every texel of the region that wasn't captured yet, 
+    // none when there is nothing left to read back
+    pub fn uncaptured(&self, cord: IVec3, texture_dim: IVec3, region: Bounds<IVec3>) -> Option<Bounds<IVec3>> {
+        let before = self.stroke.as_ref()?.get(&cord)?.before.as_ref()?;
+        if region.is_empty() {
+            return None;
+        }
+        let mut missing = Bounds::empty();
+        for texel in region.iterate_cords() {
+            let index = (texel.x + texture_dim.x * (texel.y + texture_dim.y * texel.z)) as usize;
+            if !before.captured[index] {
+                missing.encapsulate(texel);
+            }
+        }
+        (!missing.is_empty()).then_some(missing)
+    }
+
+    // keeps the texels of the region seen for the first time, texels captured earlier already hold older values
+    pub fn capture(&mut self, cord: IVec3, texture_dim: IVec3, region: &FieldRegion) {
+        let Some(before) = self.stroke.as_mut()
+            .and_then(|s| s.get_mut(&cord))
+            .and_then(|c| c.before.as_mut()) else {
+            return;
+        };
+        let mut i = 0;
+        for z in region.min.z..region.min.z + region.size.z {
+            for y in region.min.y..region.min.y + region.size.y {
+                for x in region.min.x..region.min.x + region.size.x {
+                    let index = (x + texture_dim.x * (y + texture_dim.y * z)) as usize;
+                    if !before.captured[index] {
+                        before.data[index] = region.data[i];
+                        before.captured[index] = true;
+                    }
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    pub fn touch(&mut self, cord: IVec3, texels: Bounds<IVec3>) {
+        if let Some(chunk) = self.stroke.as_mut().and_then(|s| s.get_mut(&cord)) {
+            chunk.touched.encapsulate_other(&texels);
+        }
+    }
+
+    pub fn end_stroke(&mut self, texture_dim: IVec3) {
+        let Some(mut stroke) = self.stroke.take() else {
+            return;
+        };
+
+        let mut cords: Vec<_> = stroke.keys().copied().collect();
+        cords.sort_by_key(|c| (c.z, c.y, c.x));
+
+        let chunks: Vec<_> = cords.into_iter().filter_map(|cord| {
+            let chunk = stroke.remove(&cord).unwrap();
+            match chunk.before {
+                None => Some((cord, ChunkState::Missing)),
+                Some(_) if chunk.touched.is_empty() => None,
+                Some(before) => Some((cord, ChunkState::Field(
+                    FieldRegion::crop(&before.data, texture_dim, chunk.touched)))),
+            }
+        }).collect();
+
+        if chunks.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(HistoryEntry { chunks });
+        self.fit_budget();
+    }
+
+    pub fn take_undo(&mut self) -> Option<HistoryEntry> {
+        self.undo.pop_back()
+    }
+
+    pub fn take_redo(&mut self) -> Option<HistoryEntry> {
+        self.redo.pop()
+    }
+
+    pub fn push_undo(&mut self, entry: HistoryEntry) {
+        self.undo.push_back(entry);
+        self.fit_budget();
+    }
+
+    pub fn push_redo(&mut self, entry: HistoryEntry) {
+        self.redo.push(entry);
+        self.fit_budget();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.stroke = None;
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.undo.iter().chain(self.redo.iter()).map(|e| e.byte_size()).sum()
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.fit_budget();
+    }
+
+    // oldest undo steps go first, then the furthest redo steps. 
+    // the most recent step is kept even if it alone is over budget
+    fn fit_budget(&mut self) {
+        let mut size = self.byte_size();
+        while size > self.budget && self.undo.len() + self.redo.len() > 1 {
+            let dropped = if !self.undo.is_empty() && (self.undo.len() > 1 || self.redo.is_empty()) {
+                self.undo.pop_front()
+            } else {
+                Some(self.redo.remove(0))
+            };
+            size -= dropped.map_or(0, |e| e.byte_size());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIM: IVec3 = IVec3::splat(4);
+
+    fn region(min: IVec3, size: IVec3, value: f32) -> FieldRegion {
+        FieldRegion { min, size, data: vec![value; (size.x * size.y * size.z) as usize] }
+    }
+
+    #[test]
+    fn only_uncaptured_texels_are_read_back() {
+        let cord = IVec3::ZERO;
+        let mut history = History::new(DEFAULT_HISTORY_BUDGET);
+        history.begin_stroke();
+        history.record_existing(cord, DIM);
+
+        let first = Bounds::min_max(IVec3::ZERO, IVec3::ONE);
+        let missing = history.uncaptured(cord, DIM, first).unwrap();
+        assert_eq!((missing.min(), missing.max()), (IVec3::ZERO, IVec3::ONE));
+        history.capture(cord, DIM, &region(IVec3::ZERO, IVec3::splat(2), 0.25));
+        history.touch(cord, first);
+        assert!(history.uncaptured(cord, DIM, first).is_none());
+
+        // overlapping dab only needs the new slab
+        let second = Bounds::min_max(IVec3::ZERO, IVec3::new(2, 1, 1));
+        let missing = history.uncaptured(cord, DIM, second).unwrap();
+        assert_eq!((missing.min(), missing.max()), (IVec3::new(2, 0, 0), IVec3::new(2, 1, 1)));
+        // later values of texels captured earlier are ignored
+        history.capture(cord, DIM, &region(IVec3::ZERO, IVec3::new(3, 2, 2), 0.75));
+        history.touch(cord, second);
+
+        history.end_stroke(DIM);
+        let entry = history.take_undo().unwrap();
+        let ChunkState::Field(saved) = &entry.chunks[0].1 else {
+            panic!("expected a field region");
+        };
+        assert_eq!(saved.min, IVec3::ZERO);
+        assert_eq!(saved.size, IVec3::new(3, 2, 2));
+        for (i, v) in saved.data.iter().enumerate() {
+            let expected = if i % 3 == 2 { 0.75 } else { 0.25 };
+            assert_eq!(*v, expected);
+        }
+    }
+
+    #[test]
+    fn created_chunks_are_not_read_back() {
+        let cord = IVec3::ONE;
+        let mut history = History::new(DEFAULT_HISTORY_BUDGET);
+        history.begin_stroke();
+        history.record_created(cord);
+        history.record_existing(cord, DIM);
+        assert!(history.uncaptured(cord, DIM, Bounds::min_max(IVec3::ZERO, DIM - IVec3::ONE)).is_none());
+        history.end_stroke(DIM);
+        assert!(matches!(history.take_undo().unwrap().chunks[0].1, ChunkState::Missing));
+    }
+}
@@ -6,13 +6,14 @@ use glam::{vec3, IVec3, Mat4, Vec3};
 
 use crate::{algorithms::camera::Camera, application::{app_logick::{BLOCKY, FLAT_SHADING}, cunks::{chunk::TEXTURE_OFFSET, collision_shape::COMPRESS_COLLISION, marching_cubes::WORK_GROUP, DrawParameters}, support::{shaders::{dispatch_compute_for, shaders_loader::ShaderType, ModelProgramm}, triangulation_table::static_triangle_buffer}}, dispatch_size, shader_ref};
 
-use super::{CubeMarcher, MarchParameters};
+use super::{seam_caps::SeamCaps, CubeMarcher, MarchParameters, ModelVertex};
 
 
 pub struct BlockCubeMarcher {
     command_buffer: Buffer,
     counter_buffer: Buffer,
     model_index_buffer: Buffer,
+    seam_caps: SeamCaps,
 }
 
 #[repr(C)]
@@ -52,6 +53,8 @@ struct MarchingCubesUniforms {
     num_boxes: IVec3,
     surface_level: f32,
     texture_sample_offset: IVec3,
+    scalar_field_lod: TextureUnit,
+    lod: i32,
 }
 
 #[derive(Uniforms)]
@@ -66,6 +69,11 @@ impl CubeMarcher for BlockCubeMarcher {
 
          assert!(step < 2);
 
+         if step == 0 {
+             // caps also follow lod changes of the neighbours, which leave the field untouched
+             self.seam_caps.march(params);
+         }
+
          if params.dirty_area.is_empty() {
              return;
          }
@@ -76,17 +84,21 @@ impl CubeMarcher for BlockCubeMarcher {
              params.sync_context.force_sync(BufferUpdateBarrier);
      
              params.distance_field.bind_image(1, TextureAccess::Read);
+             params.distance_field.bind(2);
+             let dirty_area = params.lod_dirty_area();
      
              let triangle_buffer = static_triangle_buffer();
              params.programm_storage.access().get::<MarchingCubeProgramm>().unwrap()
              .bind().set_uniforms(MarchingCubesUniforms { 
                  scalar_field: 1.into(), 
-                 start_block: params.dirty_area.min(),
+                 start_block: dirty_area.min(),
                  origin_offset: Vec3::ZERO, 
-                 field_scale: params.chunk_scale_factor, 
-                 num_boxes: params.num_of_cubes, 
+                 field_scale: params.chunk_scale_factor * params.lod_scale() as f32, 
+                 num_boxes: params.lod_num_of_cubes(), 
                  surface_level: params.surface_level,
                 texture_sample_offset: TEXTURE_OFFSET, 
+                scalar_field_lod: 2.into(),
+                lod: params.lod as i32,
             }).unwrap()
              // .set_buffer(&params.command_buffer, 1)
              .set_buffer(&params.model_vertex_buffer, 2)
@@ -95,7 +107,7 @@ impl CubeMarcher for BlockCubeMarcher {
              .set_buffer(params.collision_field.buffer(), 5);
              
             let c = params.sync_context.dirty(ShaderStorageBarrier);
-            dispatch_compute_for(params.dirty_area.size(), WORK_GROUP);
+            dispatch_compute_for(dirty_area.size(), WORK_GROUP);
          }
 
          else if step == 1 {
@@ -104,7 +116,7 @@ impl CubeMarcher for BlockCubeMarcher {
     
             params.programm_storage.access().get::<MarchingCubeIndexerProgramm>().unwrap()
             .bind().set_uniforms(MarchingCubesIndexerUniforms {
-                num_boxes: params.num_of_cubes,
+                num_boxes: params.lod_num_of_cubes(),
             }).unwrap()
             .set_buffer(&self.counter_buffer, 4)
             .set_buffer(&self.model_index_buffer, 2)
@@ -112,7 +124,7 @@ impl CubeMarcher for BlockCubeMarcher {
     
             params.sync_context.dirty(ShaderStorageBarrier | CommandBarrier);
     
-            dispatch_compute_for(params.num_of_cubes, WORK_GROUP);
+            dispatch_compute_for(params.lod_num_of_cubes(), WORK_GROUP);
          }
  
  
@@ -133,25 +145,60 @@ impl CubeMarcher for BlockCubeMarcher {
         params.model_vertex_buffer.unbind();
         self.model_index_buffer.unbind();
         self.command_buffer.unbind();
+
+        self.seam_caps.draw(params);
     }
     
     fn march_steps_count(&self) -> usize {
         2
     }
+
+    fn delete(&mut self) {
+        self.command_buffer.delete();
+        self.counter_buffer.delete();
+        self.model_index_buffer.delete();
+        self.seam_caps.delete();
+    }
+
+    fn read_mesh(&mut self, params: &mut MarchParameters) -> (Vec<ModelVertex>, Vec<u32>) {
+        params.sync_context.force_sync(ShaderStorageBarrier | CommandBarrier | BufferUpdateBarrier);
+
+        let command: Vec<IndirectElementsCommand> = self.command_buffer.get_all_data();
+        let count = command[0].count as usize;
+        let (mut vertices, mut indices) = if count == 0 {
+            (Vec::new(), Vec::new())
+        }
+        else {
+            let mut indices = BufferDataInterface::<u32>::get_data_slice_from_start(&self.model_index_buffer, count);
+            let first = *indices.iter().min().unwrap();
+            let last = *indices.iter().max().unwrap();
+            for i in indices.iter_mut() {
+                *i -= first;
+            }
+
+            let vertices = params.model_vertex_buffer.get_data_slice(
+                first as usize, (last - first + 1) as usize);
+            (vertices, indices)
+        };
+
+        self.seam_caps.read_mesh(params, &mut vertices, &mut indices);
+        (vertices, indices)
+    }
 }
 
 impl BlockCubeMarcher {
     pub fn new(params: &MarchParameters) -> Self {
+        let cubes = params.lod_num_of_cubes();
         let model_index_buffer = Buffer::empty::<u32>(
-            (params.num_of_cubes.x * params.num_of_cubes.y * params.num_of_cubes.z * 15) as usize, 
+            (cubes.x * cubes.y * cubes.z * 15) as usize, 
             Usage::dynamic_copy());
         let counter_buffer = Buffer::empty::<u32>(
-            (params.num_of_cubes.x * params.num_of_cubes.y * params.num_of_cubes.z) as usize,
+            (cubes.x * cubes.y * cubes.z) as usize,
             Usage::dynamic_copy());
 
         let command_buffer = Buffer::from_data(&[IndirectElementsCommand::default()], Usage::dynamic_copy());
 
-        Self { command_buffer, counter_buffer, model_index_buffer }
+        Self { command_buffer, counter_buffer, model_index_buffer, seam_caps: SeamCaps::new(params) }
 
     }
 }
\ No newline at end of file
@@ -6,7 +6,7 @@ use glam::{vec3, IVec3, Mat4, Vec3};
 
 use crate::{algorithms::camera::Camera, application::{app_logick::{BLOCKY, FLAT_SHADING}, cunks::{chunk::TEXTURE_OFFSET, collision_shape::COMPRESS_COLLISION, marching_cubes::WORK_GROUP, DrawParameters}, support::{shaders::{dispatch_compute_for, shaders_loader::ShaderType, ModelProgramm}, triangulation_table::static_triangle_buffer}}, dispatch_size, shader_ref};
 
-use super::{CubeMarcher, MarchParameters, ModelVertex};
+use super::{seam_caps::SeamCaps, CubeMarcher, MarchParameters, ModelVertex};
 
 
 #[derive(Uniforms)]
@@ -19,6 +19,8 @@ pub struct MarchingCubesUniforms {
     pub num_boxes: IVec3,
     pub surface_level: f32,
     pub texture_sample_offset: IVec3,
+    pub scalar_field_lod: TextureUnit,
+    pub lod: i32,
 }
 
 #[derive(Uniforms)]
@@ -33,11 +35,11 @@ struct ModelDisplayUniform {
 
 #[repr(C)]
 #[derive(Debug, VertexDef, Clone)]
-struct IndirectArrayCommand {
-    count: u32,
-    primitive_count: u32,
-    first: u32,
-    base_instance: u32,
+pub(super) struct IndirectArrayCommand {
+    pub count: u32,
+    pub primitive_count: u32,
+    pub first: u32,
+    pub base_instance: u32,
 }
 
 impl Default for IndirectArrayCommand {
@@ -54,6 +56,7 @@ shader_ref!(MarchingCubeProgramm, ShaderType::Compute("resources/shader_sources/
 
 pub struct FullCubeMarcher {
     command_buffer: Buffer,
+    seam_caps: SeamCaps,
 }
 
 impl CubeMarcher for FullCubeMarcher {
@@ -64,6 +67,7 @@ impl CubeMarcher for FullCubeMarcher {
         parameters.sync_context.force_sync(BufferUpdateBarrier);
 
         parameters.distance_field.bind_image(1, TextureAccess::Read);
+        parameters.distance_field.bind(2);
 
         let triangle_buffer = static_triangle_buffer();
         parameters.programm_storage.access().get::<MarchingCubeProgramm>().unwrap()
@@ -71,9 +75,11 @@ impl CubeMarcher for FullCubeMarcher {
             texture_sample_offset: TEXTURE_OFFSET,
             scalar_field: 1.into(), 
             origin_offset: Vec3::ZERO, 
-            field_scale: parameters.chunk_scale_factor, 
-            num_boxes: parameters.num_of_cubes, 
-            surface_level: parameters.surface_level }
+            field_scale: parameters.chunk_scale_factor * parameters.lod_scale() as f32, 
+            num_boxes: parameters.lod_num_of_cubes(), 
+            surface_level: parameters.surface_level,
+            scalar_field_lod: 2.into(),
+            lod: parameters.lod as i32 }
         ).unwrap()
         .set_buffer(&self.command_buffer, 1)
         .set_buffer(&parameters.model_vertex_buffer, 2)
@@ -82,7 +88,9 @@ impl CubeMarcher for FullCubeMarcher {
         .set_buffer(parameters.collision_field.buffer(), 5);
         
         parameters.sync_context.dirty(ShaderStorageBarrier | CommandBarrier);
-        dispatch_compute_for(parameters.num_of_cubes, WORK_GROUP);
+        dispatch_compute_for(parameters.lod_num_of_cubes(), WORK_GROUP);
+
+        self.seam_caps.march(parameters);
     }
     
     fn draw(&mut self, params: &mut MarchParameters) {
@@ -96,18 +104,37 @@ impl CubeMarcher for FullCubeMarcher {
 
         params.model_vertex_buffer.unbind();
         self.command_buffer.unbind();
+
+        self.seam_caps.draw(params);
     }
     
     fn march_steps_count(&self) -> usize {
         1
     }
+
+    fn delete(&mut self) {
+        self.command_buffer.delete();
+        self.seam_caps.delete();
+    }
+
+    fn read_mesh(&mut self, params: &mut MarchParameters) -> (Vec<ModelVertex>, Vec<u32>) {
+        params.sync_context.force_sync(ShaderStorageBarrier | CommandBarrier | BufferUpdateBarrier);
+
+        let command: Vec<IndirectArrayCommand> = self.command_buffer.get_all_data();
+        let count = command[0].count as usize;
+
+        let mut vertices = params.model_vertex_buffer.get_data_slice_from_start(count);
+        let mut indices = (0..count as u32).collect();
+        self.seam_caps.read_mesh(params, &mut vertices, &mut indices);
+        (vertices, indices)
+    }
 }
 
 impl FullCubeMarcher {
-    pub fn new() -> Self {
+    pub fn new(params: &MarchParameters) -> Self {
         let command_buffer: Buffer = Buffer::from_data(&[IndirectArrayCommand::default()], 
             Usage::dynamic_copy());
         
-        FullCubeMarcher { command_buffer }
+        FullCubeMarcher { command_buffer, seam_caps: SeamCaps::new(params) }
     }
 }
\ No newline at end of file
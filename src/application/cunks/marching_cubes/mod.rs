@@ -10,6 +10,7 @@ use super::{collision_shape::CollisionShape, DrawParameters, ModelVertex};
 
 pub mod full_marcher;
 pub mod block_marcher;
+pub mod seam_caps;
 
 pub const WORK_GROUP: IVec3 = IVec3 {
     x: 4,
@@ -26,7 +27,40 @@ pub struct MarchParameters {
     pub model_vertex_buffer: VertexBuffer<ModelVertex>,
     pub collision_field: CollisionShape,
     pub dirty_area: Bounds<IVec3>,
-    pub surface_level: f32
+    pub surface_level: f32,
+    // level of the distance field mip chain that is marched, each level halves the cubes count
+    pub lod: u32,
+    // bit per face of FACE_DIRECTIONS, set for faces that border a chunk marched at another lod
+    pub transition_faces: u8,
+}
+
+pub const MAX_LOD: u32 = 3;
+
+pub const FACE_DIRECTIONS: [IVec3; 6] = [
+    IVec3::NEG_X, IVec3::X,
+    IVec3::NEG_Y, IVec3::Y,
+    IVec3::NEG_Z, IVec3::Z,
+];
+
+impl MarchParameters {
+    pub fn lod_scale(&self) -> i32 {
+        1 << self.lod
+    }
+
+    pub fn lod_num_of_cubes(&self) -> IVec3 {
+        self.num_of_cubes / self.lod_scale()
+    }
+
+    // dirty area is kept in full resolution cubes
+    pub fn lod_dirty_area(&self) -> Bounds<IVec3> {
+        if self.dirty_area.is_empty() {
+            return Bounds::empty();
+        }
+        let scale = IVec3::splat(self.lod_scale());
+        Bounds::min_max(
+            self.dirty_area.min().div_euclid(scale), 
+            (self.dirty_area.max() + scale - IVec3::ONE).div_euclid(scale))
+    }
 }
 
 pub trait CubeMarcher {
@@ -36,5 +70,10 @@ pub trait CubeMarcher {
     fn march(&mut self, step: usize, params: &mut MarchParameters);
     fn draw<'a>(&mut self, params: &mut MarchParameters);
 
+    fn delete(&mut self);
+
+    // returns vertices in chunk cube space and triangle indices into them
+    fn read_mesh(&mut self, params: &mut MarchParameters) -> (Vec<ModelVertex>, Vec<u32>);
+
     // fn new(params: &MarchParameters) -> Self;
 }
\ No newline at end of file
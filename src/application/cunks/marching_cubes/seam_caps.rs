@@ -0,0 +1,123 @@
+use core::{buffers::buffer::{Buffer, BufferDataInterface, Usage, VertexBuffer}, context::synchronization_context::{BufferUpdateBarrier, CommandBarrier, ShaderStorageBarrier}, textures::{texture::TextureAccess, TextureUnit}, GL};
+use std::ffi::c_void;
+
+use egui_glfw_gl::gl;
+use glam::{IVec3, Vec3};
+
+use crate::{application::{app_logick::BLOCKY, cunks::{chunk::TEXTURE_OFFSET, marching_cubes::WORK_GROUP}, support::shaders::{dispatch_compute_for, shaders_loader::ShaderType}}, dispatch_size, shader_ref};
+
+use super::{full_marcher::IndirectArrayCommand, MarchParameters, ModelVertex};
+
+shader_ref!(SeamCapsProgramm, ShaderType::Compute("resources/shader_sources/marching_cubes/seam_caps.compute"),
+    if BLOCKY {"BLOCKY"} else {""},
+    dispatch_size!(WORK_GROUP));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/marching_cubes/seam_caps.compute")]
+struct SeamCapsUniforms {
+    #[name("scalarField")]
+    scalar_field: TextureUnit,
+    scalar_field_lod: TextureUnit,
+    lod: i32,
+    origin_offset: Vec3,
+    field_scale: Vec3,
+    texture_sample_offset: IVec3,
+    num_boxes: IVec3,
+    surface_level: f32,
+    transition_faces: i32,
+}
+
+// max triangles marching squares leaves in one face cell
+const MAX_CELL_TRIANGLES: i32 = 4;
+
+// Caps the cross section of the surface on faces that border a chunk of another lod.
+// Meshes of different resolution don't share vertices on such faces, the caps of both
+// chunks lie in the face plane and cover whatever gap is left between them.
+pub struct SeamCaps {
+    command_buffer: Buffer,
+    vertex_buffer: VertexBuffer<ModelVertex>,
+    marched_faces: u8,
+}
+
+impl SeamCaps {
+    pub fn new(params: &MarchParameters) -> Self {
+        let cubes = params.lod_num_of_cubes();
+        let face_cells = cubes.x.max(cubes.y).max(cubes.z).pow(2);
+        let vertex_buffer = VertexBuffer::empty(
+            (6 * face_cells * MAX_CELL_TRIANGLES * 3) as usize,
+            Usage::dynamic_copy());
+        let command_buffer = Buffer::from_data(&[IndirectArrayCommand::default()], Usage::dynamic_copy());
+
+        Self { command_buffer, vertex_buffer, marched_faces: 0 }
+    }
+
+    pub fn march(&mut self, params: &mut MarchParameters) {
+        if params.dirty_area.is_empty() && params.transition_faces == self.marched_faces {
+            return;
+        }
+        self.marched_faces = params.transition_faces;
+
+        self.command_buffer.update_data(0, &[IndirectArrayCommand::default()]);
+        params.sync_context.force_sync(BufferUpdateBarrier);
+        if self.marched_faces == 0 {
+            return;
+        }
+
+        params.distance_field.bind_image(1, TextureAccess::Read);
+        params.distance_field.bind(2);
+
+        let cubes = params.lod_num_of_cubes();
+        params.programm_storage.access().get::<SeamCapsProgramm>().unwrap()
+        .bind().set_uniforms(SeamCapsUniforms {
+            scalar_field: 1.into(),
+            scalar_field_lod: 2.into(),
+            lod: params.lod as i32,
+            origin_offset: Vec3::ZERO,
+            field_scale: params.chunk_scale_factor * params.lod_scale() as f32,
+            texture_sample_offset: TEXTURE_OFFSET,
+            num_boxes: cubes,
+            surface_level: params.surface_level,
+            transition_faces: self.marched_faces as i32,
+        }).unwrap()
+        .set_buffer(&self.command_buffer, 1)
+        .set_buffer(&self.vertex_buffer, 2);
+
+        params.sync_context.dirty(ShaderStorageBarrier | CommandBarrier);
+        let face_size = cubes.x.max(cubes.y).max(cubes.z);
+        dispatch_compute_for(IVec3::new(face_size, face_size, 6), WORK_GROUP);
+    }
+
+    pub fn draw(&mut self, params: &mut MarchParameters) {
+        if self.marched_faces == 0 {
+            return;
+        }
+        params.sync_context.sync(ShaderStorageBarrier | CommandBarrier);
+
+        self.vertex_buffer.bind();
+        self.command_buffer.bind(gl::DRAW_INDIRECT_BUFFER);
+        GL!(gl::DrawArraysIndirect(gl::TRIANGLES, (0) as *const c_void));
+
+        self.vertex_buffer.unbind();
+        self.command_buffer.unbind();
+    }
+
+    // appends cap triangles to a mesh read from the marcher
+    pub fn read_mesh(&mut self, params: &mut MarchParameters, vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>) {
+        if self.marched_faces == 0 {
+            return;
+        }
+        params.sync_context.force_sync(ShaderStorageBarrier | CommandBarrier | BufferUpdateBarrier);
+
+        let command: Vec<IndirectArrayCommand> = self.command_buffer.get_all_data();
+        let count = command[0].count as usize;
+
+        let first = vertices.len() as u32;
+        vertices.extend(self.vertex_buffer.get_data_slice_from_start(count));
+        indices.extend(first..first + count as u32);
+    }
+
+    pub fn delete(&mut self) {
+        self.command_buffer.delete();
+        self.vertex_buffer.delete();
+    }
+}
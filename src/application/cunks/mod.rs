@@ -6,6 +6,8 @@ pub mod chunk;
 pub mod marching_cubes;
 pub mod collision_shape;
 pub mod field;
+pub mod document;
+pub mod history;
 
 
 #[repr(C)]
@@ -0,0 +1,157 @@
+use std::io::Write;
+
+use glam::Vec3;
+
+use super::{Mesh, MeshNode};
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const TRIANGLES: u32 = 4;
+
+fn vec3_json(v: Vec3) -> String {
+    format!("[{},{},{}]", v.x, v.y, v.z)
+}
+
+struct GlbBuilder {
+    bin: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+    meshes: Vec<String>,
+    nodes: Vec<String>,
+}
+
+impl GlbBuilder {
+    fn push_view(&mut self, data: &[u8], target: u32) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(data);
+        while self.bin.len() % 4 != 0 {
+            self.bin.push(0);
+        }
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{},"target":{target}}}"#, data.len()));
+        self.buffer_views.len() - 1
+    }
+
+    fn push_vec3_accessor(&mut self, values: &[Vec3], with_bounds: bool) -> usize {
+        let bytes: Vec<u8> = values.iter()
+            .flat_map(|v| v.to_array())
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let view = self.push_view(&bytes, ARRAY_BUFFER);
+
+        let bounds = if with_bounds {
+            let min = values.iter().fold(Vec3::splat(f32::MAX), |a, b| a.min(*b));
+            let max = values.iter().fold(Vec3::splat(f32::MIN), |a, b| a.max(*b));
+            format!(r#","min":{},"max":{}"#, vec3_json(min), vec3_json(max))
+        } else {
+            String::new()
+        };
+
+        self.accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{},"type":"VEC3"{bounds}}}"#, values.len()));
+        self.accessors.len() - 1
+    }
+
+    fn push_index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.push_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        let min = indices.iter().min().copied().unwrap_or(0);
+        let max = indices.iter().max().copied().unwrap_or(0);
+        self.accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR","min":[{min}],"max":[{max}]}}"#, 
+            indices.len()));
+        self.accessors.len() - 1
+    }
+
+    fn push_node(&mut self, node: &MeshNode) {
+        let mesh = &node.mesh;
+        // glTF requires unit normals, degenerate ones are replaced by an arbitrary axis
+        let normals: Vec<Vec3> = mesh.normals.iter()
+            .map(|n| if n.length_squared() > 0. { *n } else { Vec3::Y })
+            .collect();
+
+        let position = self.push_vec3_accessor(&mesh.positions, true);
+        let normal = self.push_vec3_accessor(&normals, false);
+        let indices = self.push_index_accessor(&mesh.indices);
+
+        self.meshes.push(format!(
+            r#"{{"name":"{}","primitives":[{{"attributes":{{"POSITION":{position},"NORMAL":{normal}}},"indices":{indices},"mode":{TRIANGLES}}}]}}"#,
+            node.name));
+        self.nodes.push(format!(
+            r#"{{"name":"{}","mesh":{},"translation":{},"scale":{}}}"#,
+            node.name, self.meshes.len() - 1, vec3_json(node.translation), vec3_json(node.scale)));
+    }
+
+    fn json(&self) -> String {
+        let scene_nodes: Vec<String> = (0..self.nodes.len()).map(|i| i.to_string()).collect();
+        let mut json = format!(
+            r#"{{"asset":{{"version":"2.0","generator":"marching cubes sculpt"}},"scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}]"#,
+            scene_nodes.join(","), self.nodes.join(","));
+        if !self.meshes.is_empty() {
+            json += &format!(r#","meshes":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]"#,
+                self.meshes.join(","), self.accessors.join(","), self.buffer_views.join(","), self.bin.len());
+        }
+        json.push('}');
+        json
+    }
+}
+
+// nodes without triangles are skipped, glTF does not allow empty accessors
+// and needs at least one node in the scene
+pub fn write(nodes: &[MeshNode], w: &mut impl Write) -> Result<(), String> {
+    if nodes.iter().all(|n| n.mesh.triangle_count() == 0) {
+        return Err("nothing to export".to_owned());
+    }
+    let mut builder = GlbBuilder { 
+        bin: Vec::new(), 
+        buffer_views: Vec::new(), 
+        accessors: Vec::new(), 
+        meshes: Vec::new(), 
+        nodes: Vec::new() 
+    };
+    for node in nodes.iter().filter(|n| n.mesh.triangle_count() > 0) {
+        builder.push_node(node);
+    }
+
+    let mut json = builder.json().into_bytes();
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+
+    let mut length = 12 + 8 + json.len();
+    if !builder.bin.is_empty() {
+        length += 8 + builder.bin.len();
+    }
+    let length = u32::try_from(length).map_err(|_| "mesh is too large for glb".to_owned())?;
+
+    let mut write = || -> std::io::Result<()> {
+        for v in [GLB_MAGIC, GLB_VERSION, length, json.len() as u32, CHUNK_JSON] {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        w.write_all(&json)?;
+        if !builder.bin.is_empty() {
+            w.write_all(&(builder.bin.len() as u32).to_le_bytes())?;
+            w.write_all(&CHUNK_BIN.to_le_bytes())?;
+            w.write_all(&builder.bin)?;
+        }
+        w.flush()
+    };
+    write().map_err(|e| format!("{e}"))
+}
+
+pub fn write_mesh(mesh: &Mesh, w: &mut impl Write) -> Result<(), String> {
+    let node = MeshNode { 
+        name: "sculpt".to_owned(), 
+        mesh: mesh.clone(),
+        translation: Vec3::ZERO, 
+        scale: Vec3::ONE 
+    };
+    write(&[node], w)
+}
@@ -0,0 +1,235 @@
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+
+use glam::{IVec3, Mat4, Vec3};
+
+use super::cunks::ModelVertex;
+
+pub mod glb;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+pub mod voxelize;
+
+// distance under which two vertices are considered the same when welding, in world units
+pub const WELD_EPSILON: f32 = 1e-5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeshFormat {
+    Obj,
+    Ply,
+    Stl,
+    Glb,
+}
+
+impl MeshFormat {
+    pub const ALL: [MeshFormat; 4] = [MeshFormat::Obj, MeshFormat::Ply, MeshFormat::Stl, MeshFormat::Glb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MeshFormat::Obj => "Wavefront OBJ",
+            MeshFormat::Ply => "PLY",
+            MeshFormat::Stl => "binary STL",
+            MeshFormat::Glb => "glTF binary",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MeshFormat::Obj => "obj",
+            MeshFormat::Ply => "ply",
+            MeshFormat::Stl => "stl",
+            MeshFormat::Glb => "glb",
+        }
+    }
+}
+
+const NEIGHBOUR_CELLS: [IVec3; 27] = {
+    let mut cells = [IVec3::ZERO; 27];
+    let mut i = 0;
+    while i < 27 {
+        cells[i] = IVec3::new(i as i32 % 3 - 1, i as i32 / 3 % 3 - 1, i as i32 / 9 - 1);
+        i += 1;
+    }
+    cells
+};
+
+// triangles are counter clockwise when looking at the outside of the surface
+#[derive(Default, Clone)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u32>,
+}
+
+// mesh placed in the scene with its own transform, used by formats that keep a hierarchy
+pub struct MeshNode {
+    pub name: String,
+    pub mesh: Mesh,
+    pub translation: Vec3,
+    pub scale: Vec3,
+}
+
+impl Mesh {
+    // builds a mesh with area weighted vertex normals
+    pub fn from_triangles(positions: Vec<Vec3>, indices: Vec<u32>) -> Mesh {
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        for t in indices.chunks_exact(3) {
+            let (a, b, c) = (positions[t[0] as usize], positions[t[1] as usize], positions[t[2] as usize]);
+            let normal = (b - a).cross(c - a);
+            for i in t {
+                normals[*i as usize] += normal;
+            }
+        }
+        for n in normals.iter_mut() {
+            *n = n.normalize_or_zero();
+        }
+        Mesh { positions, normals, indices }
+    }
+
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        if self.positions.is_empty() {
+            return None;
+        }
+        let min = self.positions.iter().fold(Vec3::splat(f32::MAX), |a, b| a.min(*b));
+        let max = self.positions.iter().fold(Vec3::splat(f32::MIN), |a, b| a.max(*b));
+        Some((min, max))
+    }
+
+    pub fn transform(&mut self, matrix: Mat4) {
+        let normal_matrix = matrix.inverse().transpose();
+        for p in self.positions.iter_mut() {
+            *p = matrix.transform_point3(*p);
+        }
+        for n in self.normals.iter_mut() {
+            *n = normal_matrix.transform_vector3(*n).normalize_or_zero();
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [
+            self.positions[t[0] as usize],
+            self.positions[t[1] as usize],
+            self.positions[t[2] as usize],
+        ])
+    }
+
+    // appends marched vertices; marching cubes emits triangles clockwise 
+    // relative to the surface normal (see FlatNormal in marching_cubes.compute), so winding is flipped here
+    pub fn append_marched(&mut self, vertices: &[ModelVertex], indices: &[u32], model: Mat4) {
+        let first = self.positions.len() as u32;
+        let normal_matrix = model.inverse().transpose();
+        for v in vertices {
+            self.positions.push(model.transform_point3(v.position));
+            self.normals.push(normal_matrix.transform_vector3(v.normal).normalize_or_zero());
+        }
+        for t in indices.chunks_exact(3) {
+            self.indices.extend_from_slice(&[first + t[0], first + t[2], first + t[1]]);
+        }
+    }
+
+    // merges vertices closer than epsilon, averaging their normals, and drops 
+    // triangles that collapse in the process
+    pub fn welded(&self, epsilon: f32) -> Mesh {
+        // cells are epsilon wide, so a vertex within epsilon is in the same or a neighbouring cell
+        let mut lookup: HashMap<IVec3, Vec<u32>> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut result = Mesh::default();
+
+        for (position, normal) in self.positions.iter().zip(&self.normals) {
+            let cell = (*position / epsilon).floor().as_ivec3();
+            let mut closest: Option<(u32, f32)> = None;
+            for offset in NEIGHBOUR_CELLS {
+                for index in lookup.get(&(cell + offset)).into_iter().flatten() {
+                    let distance = result.positions[*index as usize].distance(*position);
+                    if distance <= epsilon && closest.map_or(true, |(_, d)| distance < d) {
+                        closest = Some((*index, distance));
+                    }
+                }
+            }
+
+            let index = match closest {
+                Some((index, _)) => index,
+                None => {
+                    result.positions.push(*position);
+                    result.normals.push(Vec3::ZERO);
+                    let index = (result.positions.len() - 1) as u32;
+                    lookup.entry(cell).or_default().push(index);
+                    index
+                }
+            };
+            result.normals[index as usize] += *normal;
+            remap.push(index);
+        }
+
+        for n in result.normals.iter_mut() {
+            *n = n.normalize_or_zero();
+        }
+
+        for t in self.indices.chunks_exact(3) {
+            let (a, b, c) = (remap[t[0] as usize], remap[t[1] as usize], remap[t[2] as usize]);
+            if a == b || b == c || a == c {
+                continue;
+            }
+            result.indices.extend_from_slice(&[a, b, c]);
+        }
+
+        result
+    }
+
+    // the file is only written once the whole mesh was encoded, so a refused export leaves it untouched
+    pub fn export<P: AsRef<Path>>(&self, path: P, format: MeshFormat) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        match format {
+            MeshFormat::Obj => obj::write(self, &mut bytes),
+            MeshFormat::Ply => ply::write(self, &mut bytes),
+            MeshFormat::Stl => stl::write(self, &mut bytes),
+            MeshFormat::Glb => glb::write_mesh(self, &mut bytes),
+        }?;
+        std::fs::write(path, bytes).map_err(|e| format!("{e}"))
+    }
+}
+
+// picks the reader by file extension, only OBJ and STL can be imported
+pub fn import<P: AsRef<Path>>(path: P) -> Result<Mesh, String> {
+    let path = path.as_ref();
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let file = File::open(path).map_err(|e| format!("{e}"))?;
+    let mut r = BufReader::new(file);
+    let mesh = match extension.as_str() {
+        "obj" => obj::read(&mut r)?,
+        "stl" => stl::read(&mut r)?,
+        _ => return Err(format!("can't import '.{extension}' files, expected obj or stl")),
+    };
+    if mesh.triangle_count() == 0 {
+        return Err("mesh has no triangles".to_owned());
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn weld_across_cell_boundary() {
+        // both copies of the shared edge sit on either side of a multiple of epsilon
+        let edge = WELD_EPSILON * 100.;
+        let positions = vec![
+            vec3(edge - 1e-7, 0., 0.), vec3(0., 1., 0.), vec3(0., 0., 0.),
+            vec3(edge + 1e-7, 0., 0.), vec3(0., 1., 0.), vec3(1., 1., 0.),
+        ];
+        let mesh = Mesh::from_triangles(positions, (0..6).collect());
+        let welded = mesh.welded(WELD_EPSILON);
+
+        assert_eq!(welded.positions.len(), 4);
+        assert_eq!(welded.triangle_count(), 2);
+    }
+}
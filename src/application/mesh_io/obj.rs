@@ -0,0 +1,65 @@
+use std::io::{BufRead, Write};
+
+use glam::Vec3;
+
+use super::Mesh;
+
+pub fn write(mesh: &Mesh, w: &mut impl Write) -> Result<(), String> {
+    let mut write = || -> std::io::Result<()> {
+        writeln!(w, "# {} vertices, {} triangles", mesh.positions.len(), mesh.triangle_count())?;
+        for p in &mesh.positions {
+            writeln!(w, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+        for n in &mesh.normals {
+            writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        for t in mesh.indices.chunks_exact(3) {
+            let (a, b, c) = (t[0] + 1, t[1] + 1, t[2] + 1);
+            writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+        w.flush()
+    };
+    write().map_err(|e| format!("{e}"))
+}
+
+fn parse_index(token: &str, vertex_count: usize) -> Result<u32, String> {
+    let position = token.split('/').next().unwrap_or("");
+    let index: i64 = position.parse().map_err(|_| format!("invalid face index '{token}'"))?;
+    let index = if index < 0 { vertex_count as i64 + index } else { index - 1 };
+    if index < 0 || index >= vertex_count as i64 {
+        return Err(format!("face index '{token}' is out of range"));
+    }
+    Ok(index as u32)
+}
+
+// reads vertex positions and faces, polygons are triangulated as fans
+pub fn read(r: &mut impl BufRead) -> Result<Mesh, String> {
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+
+    for (number, line) in r.lines().enumerate() {
+        let line = line.map_err(|e| format!("{e}"))?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let mut cords = [0f32; 3];
+                for c in cords.iter_mut() {
+                    *c = tokens.next().and_then(|t| t.parse().ok())
+                        .ok_or_else(|| format!("line {}: invalid vertex", number + 1))?;
+                }
+                positions.push(Vec3::from_array(cords));
+            }
+            Some("f") => {
+                let face = tokens.map(|t| parse_index(t, positions.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("line {}: {e}", number + 1))?;
+                for i in 1..face.len().saturating_sub(1) {
+                    indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Mesh::from_triangles(positions, indices))
+}
@@ -0,0 +1,34 @@
+use std::io::Write;
+
+use super::Mesh;
+
+pub fn write(mesh: &Mesh, w: &mut impl Write) -> Result<(), String> {
+    let mut write = || -> std::io::Result<()> {
+        write!(w, "ply\n\
+            format binary_little_endian 1.0\n\
+            element vertex {}\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            property float nx\n\
+            property float ny\n\
+            property float nz\n\
+            element face {}\n\
+            property list uchar uint vertex_indices\n\
+            end_header\n", mesh.positions.len(), mesh.triangle_count())?;
+
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            for v in [p.x, p.y, p.z, n.x, n.y, n.z] {
+                w.write_all(&v.to_le_bytes())?;
+            }
+        }
+        for t in mesh.indices.chunks_exact(3) {
+            w.write_all(&[3u8])?;
+            for i in t {
+                w.write_all(&i.to_le_bytes())?;
+            }
+        }
+        w.flush()
+    };
+    write().map_err(|e| format!("{e}"))
+}
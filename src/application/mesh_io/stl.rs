@@ -0,0 +1,82 @@
+use std::io::{Read, Write};
+
+use glam::Vec3;
+
+use super::Mesh;
+
+pub fn write(mesh: &Mesh, w: &mut impl Write) -> Result<(), String> {
+    let triangle_count = u32::try_from(mesh.triangle_count())
+        .map_err(|_| "too many triangles for STL".to_owned())?;
+
+    let mut write = || -> std::io::Result<()> {
+        let mut header = [0u8; 80];
+        let title = b"marching cubes sculpt";
+        header[..title.len()].copy_from_slice(title);
+        w.write_all(&header)?;
+        w.write_all(&triangle_count.to_le_bytes())?;
+
+        for [a, b, c] in mesh.triangles() {
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            for v in [normal, a, b, c] {
+                for component in v.to_array() {
+                    w.write_all(&component.to_le_bytes())?;
+                }
+            }
+            w.write_all(&0u16.to_le_bytes())?;
+        }
+        w.flush()
+    };
+    write().map_err(|e| format!("{e}"))
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Mesh, String> {
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let mut positions = Vec::with_capacity(count * 3);
+    for facet in bytes[84..].chunks_exact(50).take(count) {
+        // skip the facet normal, it is recomputed from the vertices
+        for v in facet[12..48].chunks_exact(12) {
+            let c = |i: usize| f32::from_le_bytes([v[i], v[i + 1], v[i + 2], v[i + 3]]);
+            positions.push(Vec3::new(c(0), c(4), c(8)));
+        }
+    }
+    let indices = (0..positions.len() as u32).collect();
+    Ok(Mesh::from_triangles(positions, indices))
+}
+
+fn read_ascii(text: &str) -> Result<Mesh, String> {
+    let mut positions = Vec::new();
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut cords = [0f32; 3];
+        for c in cords.iter_mut() {
+            *c = tokens.next().and_then(|t| t.parse().ok())
+                .ok_or_else(|| "invalid vertex in ascii STL".to_owned())?;
+        }
+        positions.push(Vec3::from_array(cords));
+    }
+    if positions.len() % 3 != 0 {
+        return Err("ascii STL contains an incomplete facet".to_owned());
+    }
+    let indices = (0..positions.len() as u32).collect();
+    Ok(Mesh::from_triangles(positions, indices))
+}
+
+pub fn read(r: &mut impl Read) -> Result<Mesh, String> {
+    let mut bytes = Vec::new();
+    r.read_to_end(&mut bytes).map_err(|e| format!("{e}"))?;
+
+    if bytes.len() >= 84 {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if bytes.len() == 84 + count * 50 {
+            return read_binary(&bytes);
+        }
+    }
+    if bytes.starts_with(b"solid") {
+        let text = std::str::from_utf8(&bytes).map_err(|_| "ascii STL is not valid utf-8".to_owned())?;
+        return read_ascii(text);
+    }
+    Err("not a valid STL file".to_owned())
+}
@@ -0,0 +1,327 @@
+use glam::{DVec2, IVec3, Vec3, Vec3Swizzles};
+
+// signed distances sampled on an integer lattice, negative inside the mesh
+pub struct DistanceGrid {
+    pub origin: IVec3,
+    pub size: IVec3,
+    pub distances: Vec<f32>,
+}
+
+impl DistanceGrid {
+    fn index(&self, p: IVec3) -> usize {
+        (p.x + self.size.x * (p.y + self.size.y * p.z)) as usize
+    }
+
+    pub fn max(&self) -> IVec3 {
+        self.origin + self.size - IVec3::ONE
+    }
+
+    pub fn sample(&self, p: IVec3) -> Option<f32> {
+        let local = p - self.origin;
+        if local.min_element() < 0 || (local - self.size).max_element() >= 0 {
+            return None;
+        }
+        Some(self.distances[self.index(local)])
+    }
+}
+
+fn point_triangle_distance(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0. && d2 <= 0. {
+        return p.distance(a);
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0. && d4 <= d3 {
+        return p.distance(b);
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return p.distance(a + ab * (d1 / (d1 - d3)));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0. && d5 <= d6 {
+        return p.distance(c);
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return p.distance(a + ac * (d2 / (d2 - d6)));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && (d4 - d3) >= 0. && (d5 - d6) >= 0. {
+        return p.distance(b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+
+    let denom = 1. / (va + vb + vc);
+    p.distance(a + ab * (vb * denom) + ac * (vc * denom))
+}
+
+// sign of the doubled area with deterministic tie breaking, so that a lattice line
+// passing exactly through a shared edge is counted by exactly one of the triangles
+fn orientation(a: DVec2, b: DVec2) -> (i32, f64) {
+    let area = a.y * b.x - a.x * b.y;
+    let sign = if area > 0. { 1 }
+        else if area < 0. { -1 }
+        else if b.y > a.y { 1 }
+        else if b.y < a.y { -1 }
+        else if a.x > b.x { 1 }
+        else if a.x < b.x { -1 }
+        else { 0 };
+    (sign, area)
+}
+
+fn barycentric_2d(p: DVec2, a: DVec2, b: DVec2, c: DVec2) -> Option<[f64; 3]> {
+    let (a, b, c) = (a - p, b - p, c - p);
+    let (sign_a, area_a) = orientation(b, c);
+    if sign_a == 0 {
+        return None;
+    }
+    let (sign_b, area_b) = orientation(c, a);
+    if sign_b != sign_a {
+        return None;
+    }
+    let (sign_c, area_c) = orientation(a, b);
+    if sign_c != sign_a {
+        return None;
+    }
+    let sum = area_a + area_b + area_c;
+    Some([area_a / sum, area_b / sum, area_c / sum])
+}
+
+struct Voxelizer<'a> {
+    positions: Vec<Vec3>,
+    indices: &'a [u32],
+    size: IVec3,
+    distances: Vec<f32>,
+    closest: Vec<i32>,
+}
+
+impl<'a> Voxelizer<'a> {
+    fn index(&self, p: IVec3) -> usize {
+        (p.x + self.size.x * (p.y + self.size.y * p.z)) as usize
+    }
+
+    fn triangle(&self, t: usize) -> [Vec3; 3] {
+        [
+            self.positions[self.indices[t * 3] as usize],
+            self.positions[self.indices[t * 3 + 1] as usize],
+            self.positions[self.indices[t * 3 + 2] as usize],
+        ]
+    }
+
+    fn distance_to(&self, p: IVec3, t: usize) -> f32 {
+        let [a, b, c] = self.triangle(t);
+        point_triangle_distance(p.as_vec3(), a, b, c)
+    }
+
+    fn exact_band(&mut self, t: usize) {
+        let [a, b, c] = self.triangle(t);
+        let lo = (a.min(b).min(c).floor().as_ivec3() - IVec3::ONE).max(IVec3::ZERO);
+        let hi = (a.max(b).max(c).ceil().as_ivec3() + IVec3::ONE).min(self.size - IVec3::ONE);
+
+        for z in lo.z..=hi.z {
+            for y in lo.y..=hi.y {
+                for x in lo.x..=hi.x {
+                    let p = IVec3::new(x, y, z);
+                    let d = point_triangle_distance(p.as_vec3(), a, b, c);
+                    let i = self.index(p);
+                    if d < self.distances[i] {
+                        self.distances[i] = d;
+                        self.closest[i] = t as i32;
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_neighbour(&mut self, p: IVec3, n: IVec3) {
+        let t = self.closest[self.index(n)];
+        if t < 0 {
+            return;
+        }
+        let d = self.distance_to(p, t as usize);
+        let i = self.index(p);
+        if d < self.distances[i] {
+            self.distances[i] = d;
+            self.closest[i] = t;
+        }
+    }
+
+    fn sweep(&mut self, direction: IVec3) {
+        let range = |d: i32, size: i32| -> Vec<i32> {
+            if d > 0 { (1..size).collect() } else { (0..size - 1).rev().collect() }
+        };
+        let (xs, ys, zs) = (range(direction.x, self.size.x), range(direction.y, self.size.y), range(direction.z, self.size.z));
+
+        for &z in &zs {
+            for &y in &ys {
+                for &x in &xs {
+                    let p = IVec3::new(x, y, z);
+                    let (dx, dy, dz) = (IVec3::X * direction.x, IVec3::Y * direction.y, IVec3::Z * direction.z);
+                    for offset in [dx, dy, dx + dy, dz, dx + dz, dy + dz, dx + dy + dz] {
+                        self.check_neighbour(p, p - offset);
+                    }
+                }
+            }
+        }
+    }
+
+    // parity of crossings of the +x ray through every lattice line decides the sign
+    fn crossings(&self) -> Vec<i32> {
+        let mut crossings = vec![0; self.distances.len()];
+        for t in 0..self.indices.len() / 3 {
+            let [a, b, c] = self.triangle(t);
+            let lo = a.min(b).min(c).ceil().as_ivec3().max(IVec3::ZERO);
+            let hi = a.max(b).max(c).floor().as_ivec3().min(self.size - IVec3::ONE);
+            let (a2, b2, c2) = (a.as_dvec3().yz(), b.as_dvec3().yz(), c.as_dvec3().yz());
+
+            for z in lo.z..=hi.z {
+                for y in lo.y..=hi.y {
+                    let Some(w) = barycentric_2d(DVec2::new(y as f64, z as f64), a2, b2, c2) else {
+                        continue;
+                    };
+                    let x = w[0] * a.x as f64 + w[1] * b.x as f64 + w[2] * c.x as f64;
+                    let x = (x.ceil() as i32).max(0);
+                    if x < self.size.x {
+                        crossings[self.index(IVec3::new(x, y, z))] += 1;
+                    }
+                }
+            }
+        }
+        crossings
+    }
+}
+
+// positions are expected in lattice units, margin is the number of lattice points 
+// kept around the mesh bounds. the mesh has to be closed for the sign to make sense
+pub fn voxelize(positions: &[Vec3], indices: &[u32], margin: i32) -> DistanceGrid {
+    let min = positions.iter().fold(Vec3::splat(f32::MAX), |a, b| a.min(*b));
+    let max = positions.iter().fold(Vec3::splat(f32::MIN), |a, b| a.max(*b));
+    let origin = min.floor().as_ivec3() - IVec3::splat(margin);
+    let size = max.ceil().as_ivec3() + IVec3::splat(margin) - origin + IVec3::ONE;
+    let count = (size.x * size.y * size.z) as usize;
+
+    let mut voxelizer = Voxelizer {
+        positions: positions.iter().map(|p| *p - origin.as_vec3()).collect(),
+        indices,
+        size,
+        distances: vec![f32::MAX; count],
+        closest: vec![-1; count],
+    };
+
+    for t in 0..indices.len() / 3 {
+        voxelizer.exact_band(t);
+    }
+
+    for _ in 0..2 {
+        for direction in [
+            IVec3::new(1, 1, 1), IVec3::new(-1, -1, -1),
+            IVec3::new(1, 1, -1), IVec3::new(-1, -1, 1),
+            IVec3::new(1, -1, 1), IVec3::new(-1, 1, -1),
+            IVec3::new(1, -1, -1), IVec3::new(-1, 1, 1),
+        ] {
+            voxelizer.sweep(direction);
+        }
+    }
+
+    let crossings = voxelizer.crossings();
+    let mut distances = voxelizer.distances;
+    for z in 0..size.z {
+        for y in 0..size.y {
+            let mut total = 0;
+            for x in 0..size.x {
+                let i = (x + size.x * (y + size.y * z)) as usize;
+                total += crossings[i];
+                if total % 2 == 1 {
+                    distances[i] = -distances[i];
+                }
+            }
+        }
+    }
+
+    DistanceGrid { origin, size, distances }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    // every lattice point away from the surface is negative inside and positive outside
+    fn assert_signs(positions: &[Vec3], indices: &[u32], inside: impl Fn(Vec3) -> bool) {
+        let grid = voxelize(positions, indices, 2);
+        let mut checked = (0, 0);
+        for z in 0..grid.size.z {
+            for y in 0..grid.size.y {
+                for x in 0..grid.size.x {
+                    let p = grid.origin + IVec3::new(x, y, z);
+                    let d = grid.sample(p).unwrap();
+                    if d.abs() < 1e-3 {
+                        continue;
+                    }
+                    if inside(p.as_vec3()) {
+                        assert!(d < 0., "{p} is inside but has distance {d}");
+                        checked.0 += 1;
+                    } else {
+                        assert!(d > 0., "{p} is outside but has distance {d}");
+                        checked.1 += 1;
+                    }
+                }
+            }
+        }
+        assert!(checked.0 > 0 && checked.1 > 0);
+    }
+
+    #[test]
+    fn cube_sign() {
+        // corners on the lattice, so rays run along edges and through vertices
+        let (lo, hi) = (1., 5.);
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| vec3(
+                if i & 1 == 0 { lo } else { hi },
+                if i & 2 == 0 { lo } else { hi },
+                if i & 4 == 0 { lo } else { hi }))
+            .collect();
+        let indices = vec![
+            0, 2, 3, 0, 3, 1, 4, 5, 7, 4, 7, 6,
+            0, 1, 5, 0, 5, 4, 2, 6, 7, 2, 7, 3,
+            0, 4, 6, 0, 6, 2, 1, 3, 7, 1, 7, 5,
+        ];
+        assert_signs(&positions, &indices, |p| p.cmpgt(Vec3::splat(lo)).all() && p.cmplt(Vec3::splat(hi)).all());
+
+        let grid = voxelize(&positions, &indices, 2);
+        assert!((grid.sample(IVec3::splat(3)).unwrap() + 2.).abs() < 1e-5);
+        assert!((grid.sample(IVec3::new(-1, 3, 3)).unwrap() - 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tetrahedron_sign() {
+        let positions = vec![
+            vec3(0.3, 0.2, 0.1), vec3(6.1, 0.4, 0.7), vec3(0.5, 5.9, 0.3), vec3(0.6, 0.8, 6.2),
+        ];
+        let indices = vec![0, 2, 1, 0, 1, 3, 0, 3, 2, 1, 2, 3];
+        let faces: Vec<(Vec3, Vec3)> = indices.chunks_exact(3)
+            .map(|t| {
+                let (a, b, c) = (positions[t[0] as usize], positions[t[1] as usize], positions[t[2] as usize]);
+                (a, (b - a).cross(c - a))
+            })
+            .collect();
+        // inside is on the same side of every face as the centroid
+        let side = |p: Vec3| faces.iter().map(|(a, n)| (p - *a).dot(*n).signum()).collect::<Vec<_>>();
+        let centroid_side = side(positions.iter().copied().sum::<Vec3>() / 4.);
+        assert_signs(&positions, &indices, |p| side(p) == centroid_side);
+    }
+}
@@ -3,24 +3,25 @@ use std::ops::Index;
 
 use glam::IVec3;
 
-use crate::application::app_logick::{ceil_div, NUM_OF_CUBES};
-
-pub const NUM_OF_BITMASK_VALUES: usize = ceil_div((NUM_OF_CUBES.x * NUM_OF_CUBES.y * NUM_OF_CUBES.z) as usize, 32);
+use crate::application::app_logick::ceil_div;
 
 #[derive(Debug)]
 pub struct BitField {
-    field: Box<[u32; NUM_OF_BITMASK_VALUES]>,
-    buffer: Buffer
+    field: Box<[u32]>,
+    buffer: Buffer,
+    num_of_cubes: IVec3,
 }
 
 impl BitField {
-    pub fn new() -> BitField {
-        let field = Box::new([0u32; NUM_OF_BITMASK_VALUES]);
+    pub fn new(num_of_cubes: IVec3) -> BitField {
+        let num_of_bitmask_values = ceil_div((num_of_cubes.x * num_of_cubes.y * num_of_cubes.z) as usize, 32);
+        let field = vec![0u32; num_of_bitmask_values].into_boxed_slice();
         let buffer = Buffer::from_data(field.as_ref(), Usage::dynamic_read());
         
         BitField { 
             field, 
-            buffer
+            buffer,
+            num_of_cubes,
         }
     }
 
@@ -38,8 +39,8 @@ impl BitField {
 
     pub fn get(&self, index: IVec3) -> bool {
         let position = (index.x + 
-            index.y * NUM_OF_CUBES.x + 
-            index.z * NUM_OF_CUBES.y * NUM_OF_CUBES.x) as usize;
+            index.y * self.num_of_cubes.x + 
+            index.z * self.num_of_cubes.y * self.num_of_cubes.x) as usize;
 
         let block_id = position / 32;
         let mask: u32 = 1 << (position % 32);
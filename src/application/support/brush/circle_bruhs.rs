@@ -3,11 +3,11 @@ use std::cmp::{max, min};
 
 use egui_glfw_gl::egui::Ui;
 use egui_glfw_gl::egui;
-use glam::{ivec3, vec2, IVec3, Mat4, Quat, Vec2, Vec3};
+use glam::{ivec3, IVec3, Mat4, Quat, Vec3};
 
-use crate::{algorithms::{cordinates::RoundableToIVec3, transform}, application::{cunks::field::CHUNK_SCALE_FACTOR, support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}}, shader_ref};
+use crate::{algorithms::{cordinates::RoundableToIVec3, transform}, application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, shader_ref};
 
-use super::{chunk_size_to_texture_size, chunk_to_texture_position, dispatch_compute_for, texture_space_cords, Brush, BrushApplicationParameters, BrushSettings};
+use super::{chunk_size_to_texture_size, chunk_to_texture_position, dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, texture_space_cords, Brush, BrushApplicationParameters, BrushSettings};
 
 
 shader_ref!(CirckleBrushProgramm, ShaderType::Compute("resources/shader_sources/brushes/circle_brush.compute"),
@@ -23,14 +23,14 @@ struct CircleBrushUniforms {
     start_cell: IVec3,
     // destination: TextureUnit,
     transform: Mat4,
-    falloff_strength: Vec2
+    strength: f32,
+    falloff_curve: TextureUnit,
 }
 
-#[derive(Debug, Clone)]
 pub struct CircleBrush {
     radius: f32,
     strength: f32,
-    falloff: f32,
+    falloff: Falloff,
     shader_storage: ShaderStorage
 }
 
@@ -38,7 +38,7 @@ impl CircleBrush {
     pub fn new(
         shader_storage: ShaderStorage,
     ) -> CircleBrush {
-        CircleBrush { radius: 0.1, strength: 0.01, falloff: 1., shader_storage }
+        CircleBrush { radius: 0.1, strength: 0.01, falloff: Falloff::new(FalloffPreset::Linear), shader_storage }
     }
 }
 
@@ -57,14 +57,15 @@ impl BrushSettings for CircleBrush {
             chunk_to_texture_position(parameters.transform.position(), tex_dim));
 
         source.bind_image(1, TextureAccess::ReadWrite);
+        self.falloff.bind(3);
         self.shader_storage.access().get::<CirckleBrushProgramm>().unwrap()
             .bind()
             .set_uniforms(CircleBrushUniforms {
                 source: 1.into(),
                 start_cell: dispatch_bounds.min(),
                 transform: transform.inverse(),
-                falloff_strength: vec2(self.falloff, 
-                    -self.strength * parameters.frame_rate_coefficient),
+                strength: -self.strength * parameters.frame_rate_coefficient,
+                falloff_curve: 3.into(),
             }).unwrap();
 
         dispatch_compute_for(dispatch_bounds.size());
@@ -75,21 +76,38 @@ impl BrushSettings for CircleBrush {
     }
     
     fn bounds(&self, parameters: &BrushApplicationParameters) -> crate::application::support::bounds::Bounds<Vec3> {
-        let h_size = parameters.transform.scale() * self.radius;
-        Bounds::min_max(
-            parameters.transform.position() - h_size, 
-            parameters.transform.position() + h_size)
+        parameters.bounds_for_radius(self.radius)
+    }
+    
+    fn radius(&self) -> f32 {
+        self.radius
     }
     
     fn display_ui(&mut self, ui: &mut Ui) {
         ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
         ui.add(egui::Slider::new(&mut self.strength, 0.0..=0.1).text("strength"));
-        ui.add(egui::Slider::new(&mut self.falloff, 0.1..=5.).text("sharpness"));
+        self.falloff.display_ui(ui);
     }
     
     fn brush_name(&self) -> &'static str {
         "add"
     }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("strength", self.strength);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("strength", &mut self.strength);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
 }
 
 pub struct InverseCircleBrush(CircleBrush);
@@ -115,6 +133,10 @@ impl BrushSettings for InverseCircleBrush {
         self.0.apply(source, parameters)
     }
 
+    fn radius(&self) -> f32 {
+        self.0.radius
+    }
+
     fn display_ui(&mut self, ui: &mut Ui) {
         ui.add(egui::Slider::new(&mut self.0.radius, 0.05..=0.2).text("radius"));
         
@@ -122,10 +144,32 @@ impl BrushSettings for InverseCircleBrush {
         ui.add(egui::Slider::new(&mut strength, 0.0..=0.1).text("strength"));
         self.0.strength = -strength;
 
-        ui.add(egui::Slider::new(&mut self.0.falloff, 0.1..=5.).text("sharpness"));
+        self.0.falloff.display_ui(ui);
     }
     
     fn brush_name(&self) -> &'static str {
         "remove"
     }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.0.radius);
+        values.set("strength", -self.0.strength);
+        self.0.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.0.radius);
+        if let Some(strength) = values.get::<f32>("strength") {
+            self.0.strength = -strength;
+        }
+        self.0.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.0.falloff
+    }
+
+    fn removes_material(&self) -> bool {
+        true
+    }
 }
\ No newline at end of file
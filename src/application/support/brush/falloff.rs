@@ -0,0 +1,279 @@
+use core::textures::{image_provider::ImageFormat, texture::{FilterMode, Texture, WrapMode}, TextureUnit};
+
+use egui_glfw_gl::{egui::{self, pos2, Color32, Pos2, Sense, Shape, Stroke, Ui}, gl};
+use glam::{vec2, Vec2};
+
+use super::preset::BrushValues;
+
+// samples of the curve uploaded to the shaders
+const FALLOFF_RESOLUTION: usize = 256;
+// how close to a control point in pixels the pointer has to be to pick it
+const PICK_DISTANCE: f32 = 8.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FalloffPreset {
+    Smooth,
+    Linear,
+    Sharp,
+    Constant,
+    Custom,
+}
+
+impl FalloffPreset {
+    pub const ALL: [FalloffPreset; 5] = [
+        FalloffPreset::Smooth,
+        FalloffPreset::Linear,
+        FalloffPreset::Sharp,
+        FalloffPreset::Constant,
+        FalloffPreset::Custom
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FalloffPreset::Smooth => "smooth",
+            FalloffPreset::Linear => "linear",
+            FalloffPreset::Sharp => "sharp",
+            FalloffPreset::Constant => "constant",
+            FalloffPreset::Custom => "custom",
+        }
+    }
+}
+
+// Brush weight by distance from the brush center, 0 at the center and 1 at the edge.
+// Shaders sample it from a 1d texture, see Falloff() in the brush shaders.
+pub struct Falloff {
+    preset: FalloffPreset,
+    // control points of the custom curve sorted by distance, the first one is at 0 and the last one at 1
+    points: Vec<Vec2>,
+    texture: Option<Texture>,
+    is_dirty: bool,
+    dragged_point: Option<usize>,
+}
+
+impl Falloff {
+    pub fn new(preset: FalloffPreset) -> Self {
+        let mut falloff = Self {
+            preset: FalloffPreset::Linear,
+            points: Vec::new(),
+            texture: None,
+            is_dirty: true,
+            dragged_point: None,
+        };
+        falloff.set_preset(preset);
+        falloff
+    }
+
+    pub fn set_preset(&mut self, preset: FalloffPreset) {
+        // the custom curve starts from the shape of the previous preset
+        if preset == FalloffPreset::Custom && self.preset != FalloffPreset::Custom {
+            self.points = (0..5)
+                .map(|i| {
+                    let distance = i as f32 / 4.;
+                    vec2(distance, self.evaluate(distance))
+                })
+                .collect();
+        }
+        self.preset = preset;
+        self.is_dirty = true;
+    }
+
+    pub fn save_settings(&self, values: &mut BrushValues) {
+        values.set("falloff", self.preset.name());
+        if self.preset == FalloffPreset::Custom {
+            let points: Vec<String> = self.points.iter()
+                .map(|p| format!("{}:{}", p.x, p.y))
+                .collect();
+            values.set("falloff_points", points.join(" "));
+        }
+    }
+
+    pub fn load_settings(&mut self, values: &BrushValues) {
+        let Some(preset) = values.get_str("falloff")
+            .and_then(|name| FalloffPreset::ALL.into_iter().find(|p| p.name() == name)) else {
+            return;
+        };
+
+        let points: Option<Vec<Vec2>> = values.get_str("falloff_points")
+            .map(|points| points.split_whitespace()
+                .filter_map(|p| {
+                    let (x, y) = p.split_once(':')?;
+                    Some(vec2(x.parse().ok()?, y.parse().ok()?))
+                })
+                .collect());
+
+        self.set_preset(preset);
+        if let Some(points) = points.filter(|p| p.len() >= 2 && preset == FalloffPreset::Custom) {
+            self.points = points;
+        }
+        self.is_dirty = true;
+    }
+
+    pub fn evaluate(&self, distance: f32) -> f32 {
+        Self::sample(self.preset, &self.points, distance)
+    }
+
+    fn sample(preset: FalloffPreset, points: &[Vec2], distance: f32) -> f32 {
+        let t = distance.clamp(0., 1.);
+        match preset {
+            FalloffPreset::Smooth => 1. - t * t * (3. - 2. * t),
+            FalloffPreset::Linear => 1. - t,
+            FalloffPreset::Sharp => (1. - t) * (1. - t),
+            FalloffPreset::Constant => 1.,
+            FalloffPreset::Custom => Self::sample_custom(points, t),
+        }
+    }
+
+    // catmull-rom spline through the control points
+    fn sample_custom(points: &[Vec2], t: f32) -> f32 {
+        if points.len() < 2 {
+            return 1.;
+        }
+        let segment = points.windows(2)
+            .position(|w| t <= w[1].x)
+            .unwrap_or(points.len() - 2);
+
+        let p1 = points[segment];
+        let p2 = points[segment + 1];
+        let p0 = if segment > 0 { points[segment - 1] } else { p1 * 2. - p2 };
+        let p3 = if segment + 2 < points.len() { points[segment + 2] } else { p2 * 2. - p1 };
+
+        let width = p2.x - p1.x;
+        let s = if width > f32::EPSILON { (t - p1.x) / width } else { 0. };
+        let s2 = s * s;
+        let s3 = s2 * s;
+
+        let y = 0.5 * (2. * p1.y +
+            (p2.y - p0.y) * s +
+            (2. * p0.y - 5. * p1.y + 4. * p2.y - p3.y) * s2 +
+            (3. * p1.y - p0.y - 3. * p2.y + p3.y) * s3);
+
+        y.clamp(0., 1.)
+    }
+
+    // uploads the curve when it changed and binds it for sampling
+    pub fn bind<T: Into<TextureUnit>>(&mut self, unit: T) {
+        if self.texture.is_none() {
+            self.texture = Some(Texture::new_1d()
+                .magnification_filter(FilterMode::Linear)
+                .minification_filter(FilterMode::Linear)
+                .wrap_mode_x(WrapMode::ClampToEdge)
+                .empty(FALLOFF_RESOLUTION as i32, ImageFormat {
+                    lod: 0,
+                    format: gl::RED,
+                    internal_format: gl::R32F,
+                    data_type: gl::FLOAT,
+                }));
+        }
+
+        let texture = self.texture.as_mut().unwrap();
+        if self.is_dirty {
+            let samples: Vec<f32> = (0..FALLOFF_RESOLUTION)
+                .map(|i| Self::sample(self.preset, &self.points, i as f32 / (FALLOFF_RESOLUTION - 1) as f32))
+                .collect();
+            texture.write_f32(&samples);
+            self.is_dirty = false;
+        }
+        texture.bind(unit);
+    }
+
+    pub fn display_ui(&mut self, ui: &mut Ui) {
+        let mut preset = self.preset;
+        egui::ComboBox::from_label("falloff")
+            .selected_text(preset.name())
+            .show_ui(ui, |ui| {
+                for p in FalloffPreset::ALL {
+                    ui.selectable_value(&mut preset, p, p.name());
+                }
+            }
+        );
+        if preset != self.preset {
+            self.set_preset(preset);
+        }
+
+        self.curve_editor(ui);
+    }
+
+    // drag points of the custom curve, double click adds a point, right click removes one
+    fn curve_editor(&mut self, ui: &mut Ui) {
+        let width = ui.available_width().min(200.);
+        let (response, painter) = ui.allocate_painter(egui::vec2(width, width * 0.5), Sense::click_and_drag());
+        let rect = response.rect;
+
+        let to_screen = |p: Vec2| pos2(
+            rect.min.x + p.x * rect.width(),
+            rect.max.y - p.y * rect.height());
+        let from_screen = |p: Pos2| vec2(
+            ((p.x - rect.min.x) / rect.width()).clamp(0., 1.),
+            ((rect.max.y - p.y) / rect.height()).clamp(0., 1.));
+
+        if self.preset == FalloffPreset::Custom {
+            self.edit_points(&response, &to_screen, &from_screen);
+        }
+
+        painter.rect_filled(rect, 0., Color32::from_gray(30));
+        let curve: Vec<Pos2> = (0..=64)
+            .map(|i| {
+                let distance = i as f32 / 64.;
+                to_screen(vec2(distance, self.evaluate(distance)))
+            })
+            .collect();
+        painter.add(Shape::line(curve, Stroke::new(1.5, Color32::WHITE)));
+
+        if self.preset == FalloffPreset::Custom {
+            for (i, point) in self.points.iter().enumerate() {
+                let color = if self.dragged_point == Some(i) { Color32::YELLOW } else { Color32::LIGHT_GRAY };
+                painter.circle_filled(to_screen(*point), 4., color);
+            }
+        }
+    }
+
+    fn edit_points(&mut self,
+        response: &egui::Response,
+        to_screen: &impl Fn(Vec2) -> Pos2,
+        from_screen: &impl Fn(Pos2) -> Vec2
+    ) {
+        let pointer = response.interact_pointer_pos().or(response.hover_pos());
+        let picked = pointer.and_then(|pointer| self.points.iter()
+            .enumerate()
+            .map(|(i, p)| (i, to_screen(*p).distance(pointer)))
+            .filter(|(_, d)| *d < PICK_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i));
+
+        if response.drag_started() {
+            self.dragged_point = picked;
+        }
+        if response.drag_released() {
+            self.dragged_point = None;
+        }
+
+        if let (Some(i), Some(pointer)) = (self.dragged_point, pointer) {
+            if response.dragged() {
+                let last = self.points.len() - 1;
+                let mut point = from_screen(pointer);
+                // the ends stay at the center and the edge, the rest can't pass their neighbours
+                point.x = match i {
+                    0 => 0.,
+                    _ if i == last => 1.,
+                    _ => point.x.max(self.points[i - 1].x + 0.001).min(self.points[i + 1].x - 0.001),
+                };
+                self.points[i] = point;
+                self.is_dirty = true;
+            }
+        }
+        else if let (true, Some(pointer)) = (response.double_clicked(), pointer) {
+            let point = from_screen(pointer);
+            let index = self.points.iter().position(|p| p.x > point.x).unwrap_or(self.points.len());
+            if index > 0 && index < self.points.len() {
+                self.points.insert(index, point);
+                self.is_dirty = true;
+            }
+        }
+        else if response.secondary_clicked() {
+            if let Some(i) = picked.filter(|i| *i > 0 && *i + 1 < self.points.len()) {
+                self.points.remove(i);
+                self.is_dirty = true;
+            }
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use core::{buffers::buffer::{Buffer, BufferDataInterface, Usage}, context::synchronization_context::{BufferUpdateBarrier, ShaderImageAccessBarrier, ShaderStorageBarrier, SynchronizationContext}, textures::{texture::TextureAccess, TextureUnit}, GL};
+use core::{buffers::buffer::{Buffer, BufferDataInterface, Usage}, context::synchronization_context::{BufferUpdateBarrier, ShaderImageAccessBarrier, ShaderStorageBarrier, SynchronizationContext}, textures::{texture::TextureAccess, TextureUnit}};
 
 use glam::{IVec3, Mat4, Vec3};
 
-use crate::{application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, dispatch_size, shader_ref};
+use crate::{application::{cunks::chunk::TEXTURE_OFFSET, support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}}, dispatch_size, shader_ref};
 
-use super::{chunk_size_to_texture_size, chunk_to_texture_position, dispatch_compute_for, texture_space_cords, BrushSettings};
+use super::{chunk_size_to_texture_size, chunk_to_texture_position, dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, texture_space_cords, BrushSettings};
 use egui_glfw_gl::{egui, gl};
 
 shader_ref!(FlattenCountProgramm, 
@@ -15,6 +15,15 @@ shader_ref!(FlattenApplyProgramm,
     ShaderType::Compute("resources/shader_sources/brushes/flatten_brush_apply.compute"),
     dispatch_size!(WORK_GROUP_FOR_BRUSH));
 
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/flatten_brush_count.compute")]
+struct FlattenCountUniforms {
+    source: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    tex_space_center: Vec3
+}
+
 #[derive(Uniforms)]
 #[for_shaders("resources/shader_sources/brushes/flatten_brush_apply.compute")]
 struct FlattenUniforms {
@@ -22,14 +31,20 @@ struct FlattenUniforms {
     transform: Mat4,
     start_cell: IVec3,
     strength: f32,
+    falloff_curve: TextureUnit,
     tex_space_center: Vec3
 }
 
+// count, value sum, position sum and gradient sum, all in fixed point
+const COUNTER_SIZE: usize = 8;
+
 pub struct FlattenBrush {
     sync_context: SynchronizationContext,
     shader_storage: ShaderStorage,
+    counter_buffer: Buffer,
     radius: f32,
     strenght: f32,
+    falloff: Falloff,
 }
 
 impl FlattenBrush {
@@ -37,70 +52,128 @@ impl FlattenBrush {
         sync_context: SynchronizationContext, 
         shader_storage: ShaderStorage
     ) -> FlattenBrush {
-        let counter_buffer = Buffer::from_data(&[0u32;4], Usage::dynamic_copy());
+        let counter_buffer = Buffer::from_data(&[0i32;COUNTER_SIZE], Usage::dynamic_copy());
         FlattenBrush { 
             sync_context, 
             shader_storage, 
+            counter_buffer,
             radius: 0.1, 
             strenght: 0.1, 
+            falloff: Falloff::new(FalloffPreset::Linear),
         }
     }
-}
 
+    fn texture_transform(&self, parameters: &super::BrushApplicationParameters, tex_dim: IVec3) -> (Mat4, Vec3) {
+        let tex_space_center = chunk_to_texture_position(parameters.transform.position(), tex_dim);
+        let transform = Mat4::from_scale_rotation_translation(
+            chunk_size_to_texture_size(
+            parameters.transform.scale() * self.radius, tex_dim),
+            parameters.transform.rotation(), 
+            tex_space_center
+        );
+        (transform, tex_space_center)
+    }
+}
 
 impl BrushSettings for FlattenBrush {
     fn bounds(&self, parameters: &super::BrushApplicationParameters
     ) -> Bounds<Vec3> {
-        let center = parameters.transform.position();
-        let h_size = parameters.transform.scale() * self.radius;
+        parameters.bounds_for_radius(self.radius)
+    }
 
-        Bounds::min_max(center - h_size, center + h_size)
+    fn gathers(&self) -> bool {
+        true
     }
 
-    fn apply(&mut self, source: &mut core::textures::texture::Texture, parameters: &super::BrushApplicationParameters) {
-        
-        // self.counter_buffer.update_data(0, &[0u32;4]);
-        // self.sync_context.force_sync(BufferUpdateBarrier);
+    fn begin_gather(&mut self) {
+        self.counter_buffer.update_data(0, &[0i32;COUNTER_SIZE]);
+        self.sync_context.force_sync(BufferUpdateBarrier);
+    }
 
+    // sums the surface under the brush, positions are relative to the brush center so the sums 
+    // of every chunk add up to the same plane
+    fn gather(&mut self, source: &mut core::textures::texture::Texture, parameters: &super::BrushApplicationParameters) {
         let tex_dim = source.size();
+        let (transform, tex_space_center) = self.texture_transform(parameters, tex_dim);
+
+        // texels in the border are owned by the neighbouring chunk, counting them here would count them twice
+        let region = texture_space_cords(self.bounds(parameters), tex_dim);
+        let min = region.min().max(TEXTURE_OFFSET);
+        let max = region.max().min(tex_dim - TEXTURE_OFFSET - IVec3::splat(2));
+        if (min - max).max_element() > 0 {
+            return;
+        }
 
-        let bounds = self.bounds(parameters);
-        let dispatch_bounds = texture_space_cords(bounds, tex_dim);
+        self.sync_context.sync(ShaderImageAccessBarrier);
 
-        let tex_space_center = chunk_to_texture_position(parameters.transform.position(), tex_dim);
-        let transform = Mat4::from_scale_rotation_translation(
-            chunk_size_to_texture_size(
-            parameters.transform.scale() * self.radius, tex_dim),
-            parameters.transform.rotation(), 
-            tex_space_center
-        );
+        source.bind_image(1, TextureAccess::Read);
+        self.shader_storage.access().get::<FlattenCountProgramm>().unwrap()
+            .bind()
+            .set_uniforms(FlattenCountUniforms {
+                source: 1.into(),
+                start_cell: min,
+                transform: transform.inverse(),
+                tex_space_center
+            }).unwrap()
+            .set_buffer(&self.counter_buffer, 1);
 
-        source.bind_image(1, TextureAccess::ReadWrite);
+        self.sync_context.dirty(ShaderStorageBarrier);
+        dispatch_compute_for(max - min + IVec3::ONE);
+    }
+
+    fn apply(&mut self, source: &mut core::textures::texture::Texture, parameters: &super::BrushApplicationParameters) {
+        let tex_dim = source.size();
+        let dispatch_bounds = texture_space_cords(self.bounds(parameters), tex_dim);
+        let (transform, tex_space_center) = self.texture_transform(parameters, tex_dim);
+
+        self.sync_context.sync(ShaderStorageBarrier);
+        self.sync_context.sync(ShaderImageAccessBarrier);
 
-        self.sync_context.force_sync(ShaderStorageBarrier);
-        
-        // source.bind_image(1, TextureAccess::ReadWrite);
+        source.bind_image(1, TextureAccess::ReadWrite);
+        self.falloff.bind(3);
         self.shader_storage.access().get::<FlattenApplyProgramm>().unwrap()
             .bind()
             .set_uniforms(FlattenUniforms {
                 source: 1.into(),
                 start_cell: dispatch_bounds.min(),
                 transform: transform.inverse(),
-                strength: self.strenght,
+                strength: self.strenght * parameters.frame_rate_coefficient,
+                falloff_curve: 3.into(),
                 tex_space_center
-            }).unwrap();
+            }).unwrap()
+            .set_buffer(&self.counter_buffer, 1);
 
+        self.sync_context.dirty(ShaderImageAccessBarrier);
         dispatch_compute_for(dispatch_bounds.size());
     }
 
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
     fn display_ui(&mut self, ui: &mut egui_glfw_gl::egui::Ui) {
         ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
         ui.add(egui::Slider::new(&mut self.strenght, 0.0..=0.2).text("strength"));
-        // ui.add(egui::Slider::new(&mut self.bias, 0.0..=5.).text("bias"));
-
+        self.falloff.display_ui(ui);
     }
 
     fn brush_name(&self) -> &'static str {
         "flatten"
     }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("strength", self.strenght);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("strength", &mut self.strenght);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
 }
\ No newline at end of file
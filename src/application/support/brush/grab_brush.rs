@@ -0,0 +1,231 @@
+use core::{context::synchronization_context::{ShaderImageAccessBarrier, SynchronizationContext, TextureUpdateBarrier}, textures::{texture::{Texture, TextureAccess}, TextureUnit}};
+use std::collections::HashMap;
+
+use egui_glfw_gl::egui::{self, Ui};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{algorithms::raycast::{ray_plane_intersection, Ray}, application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, dispatch_size, shader_ref};
+
+use super::{chunk_size_to_texture_size, dispatch_compute_for, falloff::{Falloff, FalloffPreset}, field_image, preset::BrushValues, texture_space_cords, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(GrabWarpProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/grab_brush_warp.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/grab_brush_warp.compute")]
+struct GrabWarpUniforms {
+    source: TextureUnit,
+    warped: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    capture_min: IVec3,
+    falloff_curve: TextureUnit,
+    motion: Vec3,
+}
+
+// field of one chunk as it was before the grab reached it
+struct FieldCapture {
+    min: IVec3,
+    size: IVec3,
+    data: Vec<f32>,
+    texture: Texture,
+}
+
+impl FieldCapture {
+    fn read(sync_context: &SynchronizationContext, source: &Texture, min: IVec3, size: IVec3) -> FieldCapture {
+        sync_context.force_sync(ShaderImageAccessBarrier | TextureUpdateBarrier);
+        let data = source.read_region_f32(0, min, size);
+        let mut texture = field_image(size);
+        texture.write_f32(&data);
+        FieldCapture { min, size, data, texture }
+    }
+
+    fn contains(&self, min: IVec3, max: IVec3) -> bool {
+        (self.min - min).max_element() <= 0 && (max - (self.min + self.size)).max_element() <= 0
+    }
+
+    // larger capture covering min..max as well, texels captured before keep their values,
+    // the new ones haven't been touched by the grab yet and are read from source
+    fn grown(mut self, sync_context: &SynchronizationContext, source: &Texture, min: IVec3, max: IVec3) -> FieldCapture {
+        let new_min = self.min.min(min);
+        let new_size = (self.min + self.size).max(max) - new_min;
+        let mut grown = FieldCapture::read(sync_context, source, new_min, new_size);
+
+        let offset = self.min - new_min;
+        for z in 0..self.size.z {
+            for y in 0..self.size.y {
+                let from = ((z * self.size.y + y) * self.size.x) as usize;
+                let to = (((z + offset.z) * new_size.y + y + offset.y) * new_size.x + offset.x) as usize;
+                grown.data[to..to + self.size.x as usize]
+                    .copy_from_slice(&self.data[from..from + self.size.x as usize]);
+            }
+        }
+        grown.texture.write_f32(&grown.data);
+
+        self.texture.delete();
+        grown
+    }
+}
+
+struct GrabState {
+    plane_normal: Vec3,
+    start: Vec3,
+    position: Vec3,
+    // scale and orientation of the dab at the grab
+    dab: BrushApplicationParameters,
+    // keyed by the chunk position, symmetry copies in the same chunk share the capture
+    captures: HashMap<[u32; 3], FieldCapture>,
+}
+
+// moves the material under the brush with the cursor, the grabbed point
+// slides on the plane facing the camera at the time of the grab.
+// every dab warps the field captured at the grab by the whole motion so far,
+// so the material doesn't blur from being resampled again on every move
+pub struct GrabBrush {
+    sync_context: SynchronizationContext,
+    shader_storage: ShaderStorage,
+    radius: f32,
+    falloff: Falloff,
+    grab: Option<GrabState>,
+}
+
+impl GrabBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> GrabBrush {
+        GrabBrush {
+            sync_context,
+            shader_storage,
+            radius: 0.1,
+            falloff: Falloff::new(FalloffPreset::Linear),
+            grab: None
+        }
+    }
+}
+
+impl BrushSettings for GrabBrush {
+    // the brush at the previous dab is covered too, texels it left go back to the captured field
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        let mut bounds = parameters.bounds_for_radius(self.radius);
+        bounds.encapsulate_other(&bounds.offset(-parameters.step));
+        bounds
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        // everything the warp can read from, the material comes from at most the whole motion back
+        let mut read_bounds = self.bounds(parameters);
+        read_bounds.encapsulate_other(&parameters.bounds_for_radius(self.radius).offset(-parameters.motion));
+        let read_bounds = texture_space_cords(read_bounds, tex_dim);
+
+        let Some(grab) = self.grab.as_mut() else {
+            return;
+        };
+
+        let key = parameters.origin.to_array().map(f32::to_bits);
+        let capture = match grab.captures.remove(&key) {
+            Some(c) if c.contains(read_bounds.min(), read_bounds.max()) => c,
+            Some(c) => c.grown(&self.sync_context, source, read_bounds.min(), read_bounds.max()),
+            None => FieldCapture::read(&self.sync_context, source, read_bounds.min(), read_bounds.size()),
+        };
+        let capture = grab.captures.entry(key).or_insert(capture);
+
+        self.sync_context.sync(ShaderImageAccessBarrier);
+        capture.texture.bind_image(1, TextureAccess::Read);
+        source.bind_image(2, TextureAccess::Write);
+
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<GrabWarpProgramm>().unwrap()
+            .bind()
+            .set_uniforms(GrabWarpUniforms {
+                source: 1.into(),
+                warped: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+                capture_min: capture.min,
+                falloff_curve: 3.into(),
+                motion: chunk_size_to_texture_size(parameters.motion, tex_dim),
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.4).text("radius"));
+        self.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "grab"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+
+    fn grabs(&self) -> bool {
+        true
+    }
+
+    fn begin_grab(&mut self, hit: Vec3, view_direction: Vec3, dab: BrushApplicationParameters) {
+        self.end_grab();
+        self.grab = Some(GrabState {
+            plane_normal: view_direction,
+            start: hit,
+            position: hit,
+            dab,
+            captures: HashMap::new(),
+        });
+    }
+
+    fn drag(&mut self, ray: Ray) -> Vec<BrushApplicationParameters> {
+        let Some(grab) = self.grab.as_mut() else {
+            return Vec::new();
+        };
+        let Some(point) = ray_plane_intersection(grab.start, grab.plane_normal, ray) else {
+            return Vec::new();
+        };
+
+        let step = point - grab.position;
+        if step == Vec3::ZERO {
+            return Vec::new();
+        }
+        grab.position = point;
+
+        vec![grab.dab.clone()
+            .with_position(point)
+            .with_motion(point - grab.start, step)]
+    }
+
+    fn end_grab(&mut self) {
+        if let Some(mut grab) = self.grab.take() {
+            for capture in grab.captures.values_mut() {
+                capture.texture.delete();
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use core::{context::synchronization_context::{ShaderImageAccessBarrier, SynchronizationContext}, textures::{texture::{Texture, TextureAccess}, TextureUnit}};
+
+use egui_glfw_gl::egui::{self, Ui};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{application::{cunks::chunk::TEXTURE_SIZE_DELTA, support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}}, dispatch_size, shader_ref};
+
+use super::{copy_from_scratch, dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, scratch_texture, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(InflateProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/inflate_brush.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/inflate_brush.compute")]
+struct InflateUniforms {
+    source: TextureUnit,
+    inflated: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    falloff_curve: TextureUnit,
+    distance: f32,
+}
+
+// pushes the surface out along its normal by the same distance everywhere under the brush,
+// unlike the add brush thin parts swell evenly instead of turning into blobs
+pub struct InflateBrush {
+    sync_context: SynchronizationContext,
+    shader_storage: ShaderStorage,
+    radius: f32,
+    // distance the surface moves per dab
+    strength: f32,
+    falloff: Falloff,
+    scratch: Option<Texture>,
+}
+
+impl InflateBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> InflateBrush {
+        InflateBrush {
+            sync_context,
+            shader_storage,
+            radius: 0.1,
+            strength: 0.002,
+            falloff: Falloff::new(FalloffPreset::Linear),
+            scratch: None
+        }
+    }
+}
+
+impl BrushSettings for InflateBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        let texels_per_unit = (tex_dim - TEXTURE_SIZE_DELTA).max_element() as f32;
+
+        self.sync_context.sync(ShaderImageAccessBarrier);
+        source.bind_image(1, TextureAccess::Read);
+        scratch_texture(&mut self.scratch, region.region_size).bind_image(2, TextureAccess::Write);
+
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<InflateProgramm>().unwrap()
+            .bind()
+            .set_uniforms(InflateUniforms {
+                source: 1.into(),
+                inflated: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+                falloff_curve: 3.into(),
+                distance: self.strength * texels_per_unit * parameters.frame_rate_coefficient,
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+
+        copy_from_scratch(&self.sync_context, &self.shader_storage, source,
+            scratch_texture(&mut self.scratch, region.region_size), region.start_cell, region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
+        ui.add(egui::Slider::new(&mut self.strength, 0.0..=0.01).text("strength"));
+        self.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "inflate"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("strength", self.strength);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("strength", &mut self.strength);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+}
+
+pub struct DeflateBrush(InflateBrush);
+
+impl DeflateBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> DeflateBrush {
+
+        let mut b = InflateBrush::new(sync_context, shader_storage);
+        b.strength = -b.strength;
+
+        DeflateBrush(b)
+    }
+}
+
+impl BrushSettings for DeflateBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        self.0.bounds(parameters)
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        self.0.apply(source, parameters)
+    }
+
+    fn radius(&self) -> f32 {
+        self.0.radius
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.0.radius, 0.05..=0.2).text("radius"));
+
+        let mut strength = - self.0.strength;
+        ui.add(egui::Slider::new(&mut strength, 0.0..=0.01).text("strength"));
+        self.0.strength = -strength;
+
+        self.0.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "deflate"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.0.radius);
+        values.set("strength", -self.0.strength);
+        self.0.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.0.radius);
+        if let Some(strength) = values.get::<f32>("strength") {
+            self.0.strength = -strength;
+        }
+        self.0.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.0.falloff
+    }
+
+    fn removes_material(&self) -> bool {
+        true
+    }
+}
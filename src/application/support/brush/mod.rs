@@ -1,15 +1,25 @@
-use core::{textures::texture::Texture, GL};
+use core::{context::synchronization_context::{ShaderImageAccessBarrier, SynchronizationContext}, textures::{image_provider::ImageFormat, texture::{FilterMode, Texture, TextureAccess}, TextureUnit}, GL};
 use std::sync::{Arc, Mutex};
 
 use egui_glfw_gl::{egui::Ui, gl};
-use glam::{IVec3, Mat4, Quat, Vec3};
+use glam::{IVec3, Mat3, Mat4, Quat, Vec3};
 
-use crate::{algorithms::{cordinates::RoundableToIVec3, transform::Transform}, application::cunks::chunk::TEXTURE_SIZE_DELTA};
+use crate::{algorithms::{cordinates::RoundableToIVec3, raycast::Ray, transform::Transform}, application::cunks::chunk::TEXTURE_SIZE_DELTA, dispatch_size, shader_ref};
 
-use super::bounds::Bounds;
+use self::{falloff::Falloff, preset::BrushValues};
+
+use super::{bounds::Bounds, shaders::shaders_loader::{ShaderStorage, ShaderType}};
 
 pub mod circle_bruhs;
 pub mod flatten_brush;
+pub mod smooth_brush;
+pub mod grab_brush;
+pub mod inflate_brush;
+pub mod pinch_brush;
+pub mod noise_brush;
+pub mod stamp_brush;
+pub mod falloff;
+pub mod preset;
 
 const WORK_GROUP_FOR_BRUSH: IVec3 = IVec3 {
     x: 8,
@@ -37,17 +47,182 @@ fn chunk_size_to_texture_size(chunk_size: Vec3, tex_dim: IVec3) -> Vec3 {
     chunk_size * (chunk_dim) / (tex_dim - Vec3::ONE)
 }
 
+// image for brushes that write a region in two passes, grown when the region doesn't fit
+fn scratch_texture(scratch: &mut Option<Texture>, size: IVec3) -> &mut Texture {
+    let fits = scratch.as_ref()
+        .map(|t| (size - t.size()).max_element() <= 0)
+        .unwrap_or(false);
+
+    if !fits {
+        let size = scratch.as_ref().map(|t| t.size().max(size)).unwrap_or(size);
+        if let Some(mut old) = scratch.take() {
+            old.delete();
+        }
+        *scratch = Some(field_image(size));
+    }
+
+    scratch.as_mut().unwrap()
+}
+
+// single channel float image in the format of the field
+fn field_image(size: IVec3) -> Texture {
+    Texture::new_3d()
+        .magnification_filter(FilterMode::Nearest)
+        .minification_filter(FilterMode::Nearest)
+        .empty(size.x, size.y, size.z, ImageFormat {
+            lod: 0,
+            format: gl::RED,
+            internal_format: gl::R32F,
+            data_type: gl::FLOAT,
+        })
+}
+
+shader_ref!(CopyRegionProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/copy_region.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/copy_region.compute")]
+struct CopyRegionUniforms {
+    source: TextureUnit,
+    scratch: TextureUnit,
+    start_cell: IVec3,
+    region_size: IVec3,
+}
+
+// second pass of the two pass brushes, waits for the scratch image and writes it into the field at start_cell
+fn copy_from_scratch(
+    sync_context: &SynchronizationContext,
+    shader_storage: &ShaderStorage,
+    source: &mut Texture,
+    scratch: &mut Texture,
+    start_cell: IVec3,
+    region_size: IVec3
+) {
+    sync_context.sync(ShaderImageAccessBarrier);
+    source.bind_image(1, TextureAccess::Write);
+    scratch.bind_image(2, TextureAccess::Read);
+
+    shader_storage.access().get::<CopyRegionProgramm>().unwrap()
+        .bind()
+        .set_uniforms(CopyRegionUniforms {
+            source: 1.into(),
+            scratch: 2.into(),
+            start_cell,
+            region_size,
+        }).unwrap();
+
+    sync_context.dirty(ShaderImageAccessBarrier);
+    dispatch_compute_for(region_size);
+}
+
+// texture space placement of a dab in one chunk
+struct BrushRegion {
+    // inverse of the brush transform, maps texture space to the unit sphere of the brush
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    tex_space_center: Vec3,
+}
+
+impl BrushRegion {
+    // none when the bounds don't reach into the texture
+    fn new(bounds: Bounds<Vec3>, radius: f32, parameters: &BrushApplicationParameters, tex_dim: IVec3) -> Option<Self> {
+        let dispatch_bounds = texture_space_cords(bounds, tex_dim);
+        let region_size = dispatch_bounds.size();
+        if region_size.min_element() <= 0 {
+            return None;
+        }
+
+        let tex_space_center = chunk_to_texture_position(parameters.transform.position(), tex_dim);
+        let transform = Mat4::from_scale_rotation_translation(
+            chunk_size_to_texture_size(
+                parameters.transform.scale() * radius, tex_dim),
+            parameters.transform.rotation(),
+            tex_space_center);
+
+        Some(Self { transform: transform.inverse(), start_cell: dispatch_bounds.min(), region_size, tex_space_center })
+    }
+}
+
+#[derive(Clone)]
 pub struct BrushApplicationParameters {
     transform: Transform,
     frame_rate_coefficient: f32,
+    // how far the material under the brush moved since the grab, zero for brushes that don't grab
+    motion: Vec3,
+    // part of the motion made since the previous dab
+    step: Vec3,
+    // world position of the space the transform is in, chunk position once the brush is made chunk local
+    origin: Vec3,
+    // surface normal under the brush center, zero when the brush wasn't placed on the surface.
+    // an oriented brush also has its local z along it
+    surface_normal: Vec3,
 }
 
 impl BrushApplicationParameters {
     pub fn new(position: Vec3, frame_rate_coefficient: f32) -> Self {
-        Self { transform: Transform::from_position(position), frame_rate_coefficient }
+        Self { transform: Transform::from_position(position), frame_rate_coefficient, motion: Vec3::ZERO, step: Vec3::ZERO, origin: Vec3::ZERO, surface_normal: Vec3::ZERO }
+    }
+
+    pub fn with_motion(mut self, motion: Vec3, step: Vec3) -> Self {
+        self.motion = motion;
+        self.step = step;
+        self
+    }
+
+    pub fn with_position(mut self, position: Vec3) -> Self {
+        self.transform.set_position(position);
+        self
+    }
+
+    // per axis scale of the brush in its local space
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.transform.set_scale(scale);
+        self
+    }
+
+    // normal of the surface the brush was placed on, without turning the brush
+    pub fn with_surface_normal(mut self, normal: Vec3) -> Self {
+        self.surface_normal = normal.normalize_or_zero();
+        self
+    }
+
+    // turns local z along the normal, local x follows the tangent when there is one
+    pub fn with_normal(mut self, normal: Vec3, tangent: Option<Vec3>) -> Self {
+        let normal = normal.normalize_or_zero();
+        if normal == Vec3::ZERO {
+            return self;
+        }
+
+        let tangent = tangent
+            .map(|t| (t - normal * t.dot(normal)).normalize_or_zero())
+            .filter(|t| *t != Vec3::ZERO);
+        let rotation = match tangent {
+            Some(tangent) => Quat::from_mat3(&Mat3::from_cols(tangent, normal.cross(tangent), normal)),
+            None => Quat::from_rotation_arc(Vec3::Z, normal),
+        };
+
+        self.transform.set_rotation(rotation);
+        self.surface_normal = normal;
+        self
+    }
+
+    pub fn surface_normal(&self) -> Vec3 {
+        self.surface_normal
+    }
+
+    // axis aligned box around the rotated and scaled brush sphere
+    pub fn bounds_for_radius(&self, radius: f32) -> Bounds<Vec3> {
+        let center = self.transform.position();
+        let axes = Mat3::from_quat(self.transform.rotation()) * Mat3::from_diagonal(self.transform.scale() * radius);
+        let h_size = Vec3::new(axes.row(0).length(), axes.row(1).length(), axes.row(2).length());
+
+        Bounds::min_max(center - h_size, center + h_size)
     }
 }
 
+#[derive(Clone)]
 pub struct Brush {
     settings: Arc<Mutex<dyn BrushSettings>>,
     parameters: BrushApplicationParameters,
@@ -65,15 +240,43 @@ impl Brush {
         self.settings.lock().unwrap().apply(source, &self.parameters);
     }
 
+    pub fn gathers(&self) -> bool {
+        self.settings.lock().unwrap().gathers()
+    }
+
+    pub fn begin_gather(&self) {
+        self.settings.lock().unwrap().begin_gather();
+    }
+
+    pub fn gather(&mut self, source: &mut Texture) {
+        self.settings.lock().unwrap().gather(source, &self.parameters);
+    }
+
     pub fn chunk_space_cords(&self, chunk_size: IVec3) -> Bounds<IVec3> {
         let bounds = self.settings.lock().unwrap().bounds(&self.parameters);
         chunk_space_cords(bounds, chunk_size)
     }
 
+    // inclusive texel bounds the brush can write to
+    pub fn texture_region(&self, tex_dim: IVec3) -> Bounds<IVec3> {
+        let region = texture_space_cords(self.bounds(), tex_dim);
+        if region.max().min_element() < 0 || (region.min() - tex_dim).max_element() >= 0 {
+            return Bounds::empty();
+        }
+        Bounds::min_max(region.min().max(IVec3::ZERO), region.max().min(tex_dim - IVec3::ONE))
+    }
+
     pub fn bounds(&self) -> Bounds<Vec3> {
         self.settings.lock().unwrap().bounds(&self.parameters)
     }
 
+    // maps the unit sphere to the space the brush covers
+    pub fn matrix(&self) -> Mat4 {
+        let radius = self.settings.lock().unwrap().radius();
+        let transform = &self.parameters.transform;
+        Mat4::from_scale_rotation_translation(transform.scale() * radius, transform.rotation(), transform.position())
+    }
+
     pub fn transformed(&self, offset: Vec3, scale: Vec3) -> Brush {
 
         let mut transform = self.parameters.transform.clone();
@@ -82,7 +285,61 @@ impl Brush {
 
         Brush { settings: self.settings.clone(), parameters: BrushApplicationParameters {
             transform,
-            frame_rate_coefficient: self.parameters.frame_rate_coefficient
+            frame_rate_coefficient: self.parameters.frame_rate_coefficient,
+            motion: self.parameters.motion * scale,
+            step: self.parameters.step * scale,
+            origin: self.parameters.origin - offset,
+            surface_normal: self.parameters.surface_normal,
+        } }
+    }
+
+    // rotates the brush position and orientation about the center
+    pub fn rotated(&self, rotation: Quat, center: Vec3) -> Brush {
+        let mut transform = self.parameters.transform.clone();
+        transform.set_position_and_rotation(
+            center + rotation * (transform.position() - center), 
+            rotation * transform.rotation());
+
+        Brush { settings: self.settings.clone(), parameters: BrushApplicationParameters {
+            transform,
+            frame_rate_coefficient: self.parameters.frame_rate_coefficient,
+            motion: rotation * self.parameters.motion,
+            step: rotation * self.parameters.step,
+            origin: self.parameters.origin,
+            surface_normal: rotation * self.parameters.surface_normal,
+        } }
+    }
+
+    // reflects the brush across the plane orthogonal to the axis (0, 1 or 2) placed at plane
+    pub fn mirrored(&self, axis: usize, plane: f32) -> Brush {
+        let mut transform = self.parameters.transform.clone();
+
+        let mut position = transform.position();
+        position[axis] = 2. * plane - position[axis];
+
+        // reflected rotation keeps the axis component and flips the other two
+        let mut rotation = transform.rotation();
+        let mut flip = Vec3::NEG_ONE;
+        flip[axis] = 1.;
+        let imaginary = Vec3::new(rotation.x, rotation.y, rotation.z) * flip;
+        rotation = Quat::from_xyzw(imaginary.x, imaginary.y, imaginary.z, rotation.w);
+
+        transform.set_position_and_rotation(position, rotation);
+
+        let mut motion = self.parameters.motion;
+        motion[axis] = -motion[axis];
+        let mut step = self.parameters.step;
+        step[axis] = -step[axis];
+        let mut surface_normal = self.parameters.surface_normal;
+        surface_normal[axis] = -surface_normal[axis];
+
+        Brush { settings: self.settings.clone(), parameters: BrushApplicationParameters {
+            transform,
+            frame_rate_coefficient: self.parameters.frame_rate_coefficient,
+            motion,
+            step,
+            origin: self.parameters.origin,
+            surface_normal,
         } }
     }
 }
@@ -122,8 +379,38 @@ fn chunk_space_cords(bounds: Bounds<Vec3>, chunk_size: IVec3) -> Bounds<IVec3> {
 
 pub trait BrushSettings {
     fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3>;
+    // radius of the brush before the transform scale is applied
+    fn radius(&self) -> f32;
     fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters);
     fn display_ui(&mut self, ui: &mut Ui);
     fn brush_name(&self) -> &'static str;
+    // parameters kept in presets, loading skips values that are missing
+    fn save_settings(&self, values: &mut BrushValues);
+    fn load_settings(&mut self, values: &BrushValues);
+    fn falloff_mut(&mut self) -> &mut Falloff;
+    // shown in the cursor color, brushes that dig into the surface
+    fn removes_material(&self) -> bool {
+        false
+    }
+
+    // gathering brushes measure the surface under the whole dab before applying it, begin_gather 
+    // is called once per dab, then gather for every chunk it reaches and apply for every chunk after that
+    fn gathers(&self) -> bool {
+        false
+    }
+    fn begin_gather(&mut self) {}
+    fn gather(&mut self, _source: &mut Texture, _parameters: &BrushApplicationParameters) {}
+
+    // grabbing brushes follow the cursor on a view aligned plane instead of placing dabs on the surface
+    fn grabs(&self) -> bool {
+        false
+    }
+    // dab holds the scale and orientation the grab keeps while it moves
+    fn begin_grab(&mut self, _hit: Vec3, _view_direction: Vec3, _dab: BrushApplicationParameters) {}
+    // moves the grabbed region to where the ray crosses the plane, empty when nothing moved
+    fn drag(&mut self, _ray: Ray) -> Vec<BrushApplicationParameters> {
+        Vec::new()
+    }
+    fn end_grab(&mut self) {}
 }
 
@@ -0,0 +1,133 @@
+use core::textures::{texture::{Texture, TextureAccess}, TextureUnit};
+
+use egui_glfw_gl::egui::{self, Ui};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{application::{cunks::chunk::{TEXTURE_OFFSET, TEXTURE_SIZE_DELTA}, support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}}, dispatch_size, shader_ref};
+
+use super::{dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(NoiseProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/noise_brush.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/noise_brush.compute")]
+struct NoiseUniforms {
+    source: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    falloff_curve: TextureUnit,
+    cell_offset: IVec3,
+    cells_per_unit: Vec3,
+    seed_offset: Vec3,
+    scale: f32,
+    octaves: i32,
+    amplitude: f32,
+}
+
+// moves the noise domain far enough for different seeds to look unrelated
+fn seed_offset(seed: u32) -> Vec3 {
+    let hash = |v: u32| {
+        let mut v = v.wrapping_mul(0x9E3779B9) ^ (v >> 16);
+        v = v.wrapping_mul(0x85EBCA6B) ^ (v >> 13);
+        (v % 10000) as f32 * 0.1
+    };
+    Vec3::new(hash(seed), hash(seed.wrapping_add(1)), hash(seed.wrapping_add(2)))
+}
+
+// roughens the surface with fractal noise in world space, for rock, bark or skin
+pub struct NoiseBrush {
+    shader_storage: ShaderStorage,
+    radius: f32,
+    falloff: Falloff,
+    // noise features per unit of world space
+    scale: f32,
+    octaves: i32,
+    amplitude: f32,
+    seed: u32,
+}
+
+impl NoiseBrush {
+    pub fn new(
+        shader_storage: ShaderStorage,
+    ) -> NoiseBrush {
+        NoiseBrush { shader_storage, radius: 0.1, falloff: Falloff::new(FalloffPreset::Linear), scale: 16., octaves: 4, amplitude: 0.01, seed: 0 }
+    }
+}
+
+impl BrushSettings for NoiseBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        // chunks lie on whole units, so the first texel of every chunk falls on a whole cell
+        let cells_per_unit = (tex_dim - TEXTURE_SIZE_DELTA).as_vec3();
+        let cell_offset = (parameters.origin * cells_per_unit).round().as_ivec3() - TEXTURE_OFFSET;
+
+        source.bind_image(1, TextureAccess::ReadWrite);
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<NoiseProgramm>().unwrap()
+            .bind()
+            .set_uniforms(NoiseUniforms {
+                source: 1.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                falloff_curve: 3.into(),
+                cell_offset,
+                cells_per_unit,
+                seed_offset: seed_offset(self.seed),
+                scale: self.scale,
+                octaves: self.octaves,
+                amplitude: self.amplitude * parameters.frame_rate_coefficient,
+            }).unwrap();
+
+        dispatch_compute_for(region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
+        ui.add(egui::Slider::new(&mut self.amplitude, 0.0..=0.05).text("amplitude"));
+        ui.add(egui::Slider::new(&mut self.scale, 1.0..=64.).text("scale"));
+        ui.add(egui::Slider::new(&mut self.octaves, 1..=8).text("octaves"));
+        self.falloff.display_ui(ui);
+        ui.add(egui::DragValue::new(&mut self.seed).prefix("seed: "));
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "noise"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("amplitude", self.amplitude);
+        values.set("scale", self.scale);
+        values.set("octaves", self.octaves);
+        values.set("seed", self.seed);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("amplitude", &mut self.amplitude);
+        values.load_into("scale", &mut self.scale);
+        values.load_into("octaves", &mut self.octaves);
+        values.load_into("seed", &mut self.seed);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+}
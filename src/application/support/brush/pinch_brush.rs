@@ -0,0 +1,245 @@
+use core::{context::synchronization_context::{ShaderImageAccessBarrier, SynchronizationContext}, textures::{texture::{Texture, TextureAccess}, TextureUnit}};
+
+use egui_glfw_gl::egui::{self, Ui};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, dispatch_size, shader_ref};
+
+use super::{copy_from_scratch, dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, scratch_texture, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(PinchProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/pinch_brush.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+shader_ref!(CreaseProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/pinch_brush.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH),
+    "CREASE");
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/pinch_brush.compute")]
+struct PinchUniforms {
+    source: TextureUnit,
+    pinched: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    falloff_curve: TextureUnit,
+    tex_space_center: Vec3,
+    surface_normal: Vec3,
+    pinch: f32,
+}
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/pinch_brush.compute")]
+struct CreaseUniforms {
+    source: TextureUnit,
+    pinched: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    falloff_curve: TextureUnit,
+    tex_space_center: Vec3,
+    surface_normal: Vec3,
+    pinch: f32,
+    depth: f32,
+}
+
+// pulls the surface towards the line through the brush center along the surface normal
+pub struct PinchBrush {
+    sync_context: SynchronizationContext,
+    shader_storage: ShaderStorage,
+    radius: f32,
+    pinch: f32,
+    falloff: Falloff,
+    scratch: Option<Texture>,
+}
+
+impl PinchBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> PinchBrush {
+        PinchBrush {
+            sync_context,
+            shader_storage,
+            radius: 0.1,
+            pinch: 0.1,
+            falloff: Falloff::new(FalloffPreset::Linear),
+            scratch: None
+        }
+    }
+}
+
+impl BrushSettings for PinchBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        self.sync_context.sync(ShaderImageAccessBarrier);
+        source.bind_image(1, TextureAccess::Read);
+        scratch_texture(&mut self.scratch, region.region_size).bind_image(2, TextureAccess::Write);
+
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<PinchProgramm>().unwrap()
+            .bind()
+            .set_uniforms(PinchUniforms {
+                source: 1.into(),
+                pinched: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+                falloff_curve: 3.into(),
+                tex_space_center: region.tex_space_center,
+                surface_normal: parameters.surface_normal(),
+                pinch: self.pinch * parameters.frame_rate_coefficient,
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+
+        copy_from_scratch(&self.sync_context, &self.shader_storage, source,
+            scratch_texture(&mut self.scratch, region.region_size), region.start_cell, region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
+        ui.add(egui::Slider::new(&mut self.pinch, 0.0..=0.5).text("pinch"));
+        self.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "pinch"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("pinch", self.pinch);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("pinch", &mut self.pinch);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+}
+
+// removes material like the remove brush and pinches the sides of the cut,
+// dragged along a stroke it leaves a groove with a sharp bottom
+pub struct CreaseBrush {
+    sync_context: SynchronizationContext,
+    shader_storage: ShaderStorage,
+    radius: f32,
+    depth: f32,
+    pinch: f32,
+    falloff: Falloff,
+    scratch: Option<Texture>,
+}
+
+impl CreaseBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> CreaseBrush {
+        CreaseBrush {
+            sync_context,
+            shader_storage,
+            radius: 0.05,
+            depth: 0.01,
+            pinch: 0.2,
+            falloff: Falloff::new(FalloffPreset::Sharp),
+            scratch: None
+        }
+    }
+}
+
+impl BrushSettings for CreaseBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        self.sync_context.sync(ShaderImageAccessBarrier);
+        source.bind_image(1, TextureAccess::Read);
+        scratch_texture(&mut self.scratch, region.region_size).bind_image(2, TextureAccess::Write);
+
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<CreaseProgramm>().unwrap()
+            .bind()
+            .set_uniforms(CreaseUniforms {
+                source: 1.into(),
+                pinched: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+                falloff_curve: 3.into(),
+                tex_space_center: region.tex_space_center,
+                surface_normal: parameters.surface_normal(),
+                pinch: self.pinch * parameters.frame_rate_coefficient,
+                depth: self.depth * parameters.frame_rate_coefficient,
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+
+        copy_from_scratch(&self.sync_context, &self.shader_storage, source,
+            scratch_texture(&mut self.scratch, region.region_size), region.start_cell, region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.02..=0.2).text("radius"));
+        ui.add(egui::Slider::new(&mut self.depth, 0.0..=0.1).text("depth"));
+        ui.add(egui::Slider::new(&mut self.pinch, 0.0..=0.5).text("pinch"));
+        self.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "crease"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("depth", self.depth);
+        values.set("pinch", self.pinch);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("depth", &mut self.depth);
+        values.load_into("pinch", &mut self.pinch);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+
+    fn removes_material(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,218 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+// Parameters of one brush by name, stored as text so presets keep loading
+// after a brush gains or loses parameters. Missing values keep the current ones.
+#[derive(Debug, Clone, Default)]
+pub struct BrushValues {
+    values: Vec<(String, String)>,
+}
+
+impl BrushValues {
+    pub fn set<T: ToString>(&mut self, key: &str, value: T) {
+        let value = value.to_string();
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((key.to_owned(), value)),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.values.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.get_str(key)?.parse().ok()
+    }
+
+    // overwrites the value only when the key is present and parses
+    pub fn load_into<T: std::str::FromStr>(&self, key: &str, value: &mut T) {
+        if let Some(v) = self.get(key) {
+            *value = v;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BrushPreset {
+    pub name: String,
+    // BrushSettings::brush_name of the brush the values belong to
+    pub brush: String,
+    pub values: BrushValues,
+}
+
+// File layout, one preset per section:
+//   [preset name]
+//   brush = add
+//   radius = 0.1
+pub struct PresetLibrary {
+    path: PathBuf,
+    presets: Vec<BrushPreset>,
+}
+
+impl PresetLibrary {
+    pub fn empty(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), presets: Vec::new() }
+    }
+
+    // a missing file gives an empty library, it's created by the first save
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("{e}")),
+        };
+
+        let mut presets: Vec<BrushPreset> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                presets.push(BrushPreset { name: name.to_owned(), brush: String::new(), values: BrushValues::default() });
+                continue;
+            }
+
+            let (Some(preset), Some((key, value))) = (presets.last_mut(), line.split_once('=')) else {
+                return Err(format!("{}:{}: expected [name] or key = value", path.display(), number + 1));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if key == "brush" {
+                preset.brush = value.to_owned();
+            }
+            else {
+                preset.values.set(key, value);
+            }
+        }
+
+        Ok(Self { path, presets })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut text = String::new();
+        for preset in &self.presets {
+            text += &format!("[{}]\nbrush = {}\n", preset.name, preset.brush);
+            for (key, value) in &preset.values.values {
+                text += &format!("{key} = {value}\n");
+            }
+            text += "\n";
+        }
+        fs::write(&self.path, text).map_err(|e| format!("{e}"))
+    }
+
+    pub fn presets(&self) -> &[BrushPreset] {
+        &self.presets
+    }
+
+    // replaces a preset with the same name
+    pub fn store(&mut self, preset: BrushPreset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(p) => *p = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<(), String> {
+        if self.presets.iter().enumerate().any(|(i, p)| i != index && p.name == name) {
+            return Err(format!("preset {name} already exists"));
+        }
+        let preset = self.presets.get_mut(index).ok_or("no preset selected")?;
+        preset.name = name.to_owned();
+        Ok(())
+    }
+
+    pub fn delete(&mut self, index: usize) {
+        if index < self.presets.len() {
+            self.presets.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // file in the temp dir, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("preset_test_{}_{name}.txt", std::process::id())))
+        }
+
+        fn with_text(name: &str, text: &str) -> Self {
+            let file = Self::new(name);
+            fs::write(&file.0, text).unwrap();
+            file
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn preset(name: &str, brush: &str, values: &[(&str, &str)]) -> BrushPreset {
+        let mut preset = BrushPreset { name: name.to_owned(), brush: brush.to_owned(), values: BrushValues::default() };
+        for (key, value) in values {
+            preset.values.set(key, value);
+        }
+        preset
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = TempFile::new("round_trip");
+        let mut library = PresetLibrary::empty(&file.0);
+        library.store(preset("soft add", "add", &[("radius", "0.1"), ("strength", "0.5")]));
+        library.store(preset("rough", "noise", &[("octaves", "4")]));
+        library.save().unwrap();
+
+        let loaded = PresetLibrary::load(&file.0).unwrap();
+        let names: Vec<_> = loaded.presets().iter().map(|p| (p.name.as_str(), p.brush.as_str())).collect();
+        assert_eq!(names, [("soft add", "add"), ("rough", "noise")]);
+        assert_eq!(loaded.presets()[0].values.get::<f32>("radius"), Some(0.1));
+        assert_eq!(loaded.presets()[0].values.get::<f32>("strength"), Some(0.5));
+        assert_eq!(loaded.presets()[1].values.get::<u32>("octaves"), Some(4));
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        let file = TempFile::new("missing");
+        assert!(PresetLibrary::load(&file.0).unwrap().presets().is_empty());
+    }
+
+    #[test]
+    fn load_into_keeps_missing_and_unparsable_values() {
+        let values = preset("p", "add", &[("radius", "0.2"), ("octaves", "many")]).values;
+
+        let mut radius = 0.1f32;
+        let mut strength = 0.5f32;
+        let mut octaves = 3u32;
+        values.load_into("radius", &mut radius);
+        values.load_into("strength", &mut strength);
+        values.load_into("octaves", &mut octaves);
+
+        assert_eq!(radius, 0.2);
+        assert_eq!(strength, 0.5);
+        assert_eq!(octaves, 3);
+    }
+
+    #[test]
+    fn key_before_section_reports_the_line() {
+        let file = TempFile::with_text("key_first", "# presets\n\nradius = 0.1\n[p]\n");
+        let error = PresetLibrary::load(&file.0).err().unwrap();
+        assert!(error.ends_with(":3: expected [name] or key = value"), "{error}");
+    }
+
+    #[test]
+    fn line_without_equals_reports_the_line() {
+        let file = TempFile::with_text("no_equals", "[p]\nbrush = add\nradius 0.1\n");
+        let error = PresetLibrary::load(&file.0).err().unwrap();
+        assert!(error.ends_with(":3: expected [name] or key = value"), "{error}");
+    }
+}
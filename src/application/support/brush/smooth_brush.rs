@@ -0,0 +1,144 @@
+use core::{context::synchronization_context::{ShaderImageAccessBarrier, SynchronizationContext}, textures::{texture::{Texture, TextureAccess}, TextureUnit}};
+
+use egui_glfw_gl::egui::{self, Ui};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, dispatch_size, shader_ref};
+
+use super::{dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, scratch_texture, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(SmoothBlurProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/smooth_brush_blur.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+shader_ref!(SmoothApplyProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/smooth_brush_apply.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/smooth_brush_blur.compute")]
+struct SmoothBlurUniforms {
+    source: TextureUnit,
+    blurred: TextureUnit,
+    start_cell: IVec3,
+    region_size: IVec3,
+}
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/smooth_brush_apply.compute")]
+struct SmoothApplyUniforms {
+    source: TextureUnit,
+    blurred: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    region_size: IVec3,
+    strength: f32,
+    falloff_curve: TextureUnit,
+}
+
+// relaxes the field inside the brush sphere towards the average of its neighbours.
+// the relaxed values go through a scratch image first, so the pass doesn't read texels it already wrote
+pub struct SmoothBrush {
+    sync_context: SynchronizationContext,
+    shader_storage: ShaderStorage,
+    radius: f32,
+    strength: f32,
+    falloff: Falloff,
+    scratch: Option<Texture>,
+}
+
+impl SmoothBrush {
+    pub fn new(
+        sync_context: SynchronizationContext,
+        shader_storage: ShaderStorage
+    ) -> SmoothBrush {
+        SmoothBrush {
+            sync_context,
+            shader_storage,
+            radius: 0.1,
+            strength: 0.5,
+            falloff: Falloff::new(FalloffPreset::Linear),
+            scratch: None
+        }
+    }
+}
+
+impl BrushSettings for SmoothBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let tex_dim = source.size();
+
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        self.sync_context.sync(ShaderImageAccessBarrier);
+        source.bind_image(1, TextureAccess::Read);
+        scratch_texture(&mut self.scratch, region.region_size).bind_image(2, TextureAccess::Write);
+
+        self.shader_storage.access().get::<SmoothBlurProgramm>().unwrap()
+            .bind()
+            .set_uniforms(SmoothBlurUniforms {
+                source: 1.into(),
+                blurred: 2.into(),
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+        self.sync_context.sync(ShaderImageAccessBarrier);
+
+        source.bind_image(1, TextureAccess::ReadWrite);
+        scratch_texture(&mut self.scratch, region.region_size).bind_image(2, TextureAccess::Read);
+
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<SmoothApplyProgramm>().unwrap()
+            .bind()
+            .set_uniforms(SmoothApplyUniforms {
+                source: 1.into(),
+                blurred: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                region_size: region.region_size,
+                strength: self.strength * parameters.frame_rate_coefficient,
+                falloff_curve: 3.into(),
+            }).unwrap();
+
+        self.sync_context.dirty(ShaderImageAccessBarrier);
+        dispatch_compute_for(region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.2).text("radius"));
+        ui.add(egui::Slider::new(&mut self.strength, 0.0..=1.).text("strength"));
+        self.falloff.display_ui(ui);
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "smooth"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("strength", self.strength);
+        self.falloff.save_settings(values);
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("strength", &mut self.strength);
+        self.falloff.load_settings(values);
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+}
@@ -0,0 +1,229 @@
+use core::{textures::{image_provider::Image, texture::{with_pixel_store, FilterMode, Texture, TextureAccess, WrapMode}, TextureUnit}};
+use std::fs;
+
+use egui_glfw_gl::{egui::{self, Ui}, gl};
+use glam::{IVec3, Mat4, Vec3};
+
+use crate::{application::support::{bounds::Bounds, brush::WORK_GROUP_FOR_BRUSH, shaders::shaders_loader::{ShaderStorage, ShaderType}}, dispatch_size, shader_ref};
+
+use super::{dispatch_compute_for, falloff::{Falloff, FalloffPreset}, preset::BrushValues, BrushApplicationParameters, BrushRegion, BrushSettings};
+
+shader_ref!(StampProgramm,
+    ShaderType::Compute("resources/shader_sources/brushes/stamp_brush.compute"),
+    dispatch_size!(WORK_GROUP_FOR_BRUSH));
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/brushes/stamp_brush.compute")]
+struct StampUniforms {
+    source: TextureUnit,
+    stamp: TextureUnit,
+    transform: Mat4,
+    start_cell: IVec3,
+    surface_normal: Vec3,
+    falloff_curve: TextureUnit,
+    strength: f32,
+    stamp_rotation: f32,
+    stamp_scale: f32,
+    tiling: i32,
+}
+
+const STAMP_DIRECTORY: &str = "resources/images";
+
+fn list_images() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(STAMP_DIRECTORY) else {
+        return Vec::new();
+    };
+    let mut images: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| matches!(e.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "bmp" | "tga"))
+            .unwrap_or(false))
+        .filter_map(|p| p.to_str().map(|p| p.to_owned()))
+        .collect();
+    images.sort();
+    images
+}
+
+fn load_stamp(path: &str) -> Result<Texture, String> {
+    let image = Image::grayscale_from_file(path)?;
+
+    // rows of a single channel image are not padded to 4 bytes
+    Ok(with_pixel_store(gl::UNPACK_ALIGNMENT, 1, || Texture::new_2d()
+        .magnification_filter(FilterMode::Linear)
+        .minification_filter(FilterMode::Linear)
+        .wrap_mode_x(WrapMode::Repeat)
+        .wrap_mode_y(WrapMode::Repeat)
+        .with_data(&image.to_gl(gl::RED, gl::UNSIGNED_BYTE))))
+}
+
+// adds material in the shape of a grayscale image laid on the surface, brighter pixels raise it more
+pub struct StampBrush {
+    shader_storage: ShaderStorage,
+    radius: f32,
+    strength: f32,
+    falloff: Falloff,
+    // degrees around the surface normal
+    rotation: f32,
+    scale: f32,
+    tiling: bool,
+    images: Vec<String>,
+    selected_image: usize,
+    stamp: Option<(usize, Texture)>,
+    // image that failed to load, it's only tried again once another image was selected
+    failed_image: Option<usize>,
+    status: String,
+}
+
+impl StampBrush {
+    pub fn new(
+        shader_storage: ShaderStorage,
+    ) -> StampBrush {
+        StampBrush {
+            shader_storage,
+            radius: 0.1,
+            strength: 0.02,
+            falloff: Falloff::new(FalloffPreset::Linear),
+            rotation: 0.,
+            scale: 1.,
+            tiling: false,
+            images: list_images(),
+            selected_image: 0,
+            stamp: None,
+            failed_image: None,
+            status: String::new(),
+        }
+    }
+
+    // texture of the selected image, loaded again when the selection changes
+    fn stamp_texture(&mut self) -> Option<&mut Texture> {
+        let path = self.images.get(self.selected_image)?;
+        if self.failed_image == Some(self.selected_image) {
+            return None;
+        }
+        if self.stamp.as_ref().map(|(i, _)| *i) != Some(self.selected_image) {
+            if let Some((_, mut old)) = self.stamp.take() {
+                old.delete();
+            }
+            match load_stamp(path) {
+                Ok(texture) => {
+                    self.stamp = Some((self.selected_image, texture));
+                    self.failed_image = None;
+                    self.status.clear();
+                },
+                Err(e) => {
+                    self.failed_image = Some(self.selected_image);
+                    self.status = e;
+                    return None;
+                }
+            }
+        }
+        self.stamp.as_mut().map(|(_, t)| t)
+    }
+}
+
+impl BrushSettings for StampBrush {
+    fn bounds(&self, parameters: &BrushApplicationParameters) -> Bounds<Vec3> {
+        parameters.bounds_for_radius(self.radius)
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn apply(&mut self, source: &mut Texture, parameters: &BrushApplicationParameters) {
+        let Some(stamp) = self.stamp_texture() else {
+            return;
+        };
+        stamp.bind(2);
+
+        let tex_dim = source.size();
+
+        let Some(region) = BrushRegion::new(self.bounds(parameters), self.radius, parameters, tex_dim) else {
+            return;
+        };
+
+        source.bind_image(1, TextureAccess::ReadWrite);
+        self.falloff.bind(3);
+        self.shader_storage.access().get::<StampProgramm>().unwrap()
+            .bind()
+            .set_uniforms(StampUniforms {
+                source: 1.into(),
+                stamp: 2.into(),
+                transform: region.transform,
+                start_cell: region.start_cell,
+                surface_normal: parameters.surface_normal(),
+                falloff_curve: 3.into(),
+                strength: -self.strength * parameters.frame_rate_coefficient,
+                stamp_rotation: self.rotation.to_radians(),
+                stamp_scale: self.scale,
+                tiling: self.tiling as i32,
+            }).unwrap();
+
+        dispatch_compute_for(region.region_size);
+    }
+
+    fn display_ui(&mut self, ui: &mut Ui) {
+        let selected = self.images.get(self.selected_image)
+            .map(|p| p.as_str())
+            .unwrap_or("no images");
+        egui::ComboBox::from_label("stamp")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (i, path) in self.images.iter().enumerate() {
+                    ui.selectable_value(&mut self.selected_image, i, path.as_str());
+                }
+            }
+        );
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+
+        ui.add(egui::Slider::new(&mut self.radius, 0.05..=0.4).text("radius"));
+        ui.add(egui::Slider::new(&mut self.strength, -0.1..=0.1).text("strength"));
+        self.falloff.display_ui(ui);
+        ui.add(egui::Slider::new(&mut self.rotation, 0.0..=360.).text("rotation"));
+        ui.add(egui::Slider::new(&mut self.scale, 0.1..=2.).text("scale"));
+        ui.checkbox(&mut self.tiling, "tiling");
+    }
+
+    fn brush_name(&self) -> &'static str {
+        "stamp"
+    }
+
+    fn save_settings(&self, values: &mut BrushValues) {
+        values.set("radius", self.radius);
+        values.set("strength", self.strength);
+        self.falloff.save_settings(values);
+        values.set("rotation", self.rotation);
+        values.set("scale", self.scale);
+        values.set("tiling", self.tiling);
+        if let Some(image) = self.images.get(self.selected_image) {
+            values.set("image", image);
+        }
+    }
+
+    fn load_settings(&mut self, values: &BrushValues) {
+        values.load_into("radius", &mut self.radius);
+        values.load_into("strength", &mut self.strength);
+        self.falloff.load_settings(values);
+        values.load_into("rotation", &mut self.rotation);
+        values.load_into("scale", &mut self.scale);
+        values.load_into("tiling", &mut self.tiling);
+        if let Some(image) = values.get_str("image") {
+            match self.images.iter().position(|i| i == image) {
+                Some(i) => self.selected_image = i,
+                None => self.status = format!("stamp image {image} not found"),
+            }
+        }
+    }
+
+    fn falloff_mut(&mut self) -> &mut Falloff {
+        &mut self.falloff
+    }
+
+    fn removes_material(&self) -> bool {
+        self.strength < 0.
+    }
+}
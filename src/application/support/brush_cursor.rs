@@ -0,0 +1,102 @@
+use core::{buffers::buffer::{Buffer, Usage, VertexBuffer}, textures::TextureUnit, GL};
+use std::ffi::c_void;
+
+use egui_glfw_gl::gl;
+use glam::{vec2, Mat4, Vec2, Vec4};
+
+use crate::{algorithms::camera::Camera, shader_ref};
+
+use super::{brush::BrushSettings, shaders::shaders_loader::{ShaderStorage, ShaderType}};
+
+shader_ref!(BrushCursorProgramm, ShaderType::Model {
+    vertex: "resources/shader_sources/drawing/brush_cursor.vert",
+    fragment: "resources/shader_sources/drawing/brush_cursor.frag"
+});
+
+#[derive(Uniforms)]
+#[for_shaders("resources/shader_sources/drawing/brush_cursor.vert",
+              "resources/shader_sources/drawing/brush_cursor.frag")]
+struct BrushCursorUniforms {
+    view: Mat4,
+    projection: Mat4,
+    model: Mat4,
+    falloff_curve: TextureUnit,
+    color: Vec4,
+}
+
+#[repr(C)]
+#[derive(VertexDef)]
+struct CursorVertex {
+    position: Vec2,
+}
+
+const ADD_COLOR: Vec4 = Vec4::new(0.35, 0.75, 1., 0.9);
+const REMOVE_COLOR: Vec4 = Vec4::new(1., 0.4, 0.3, 0.9);
+// symmetry copies are drawn fainter than the cursor itself
+const COPY_OPACITY: f32 = 0.5;
+
+// disc laid on the surface under the pointer, the ring marks the brush edge
+// and the inside fades with the brush falloff
+pub struct BrushCursor {
+    programm_storage: ShaderStorage,
+    quad: Option<(VertexBuffer<CursorVertex>, Buffer)>,
+}
+
+impl BrushCursor {
+    pub fn new(programm_storage: ShaderStorage) -> Self {
+        Self { programm_storage, quad: None }
+    }
+
+    // matrices map the unit sphere to the brush, the first one is the cursor and the rest are symmetry copies
+    pub fn draw(&mut self, camera: &impl Camera, settings: &mut dyn BrushSettings, matrices: &[Mat4]) {
+        let (vertex, index) = self.quad.get_or_insert_with(|| {
+            let vertices = [
+                CursorVertex { position: vec2(-1., -1.) },
+                CursorVertex { position: vec2(1., -1.) },
+                CursorVertex { position: vec2(1., 1.) },
+                CursorVertex { position: vec2(-1., 1.) },
+            ];
+            (VertexBuffer::from_data(&vertices, Usage::static_draw()),
+                Buffer::from_data(&[0, 1, 2, 0, 2, 3u32], Usage::static_draw()))
+        });
+
+        let base_color = if settings.removes_material() { REMOVE_COLOR } else { ADD_COLOR };
+        settings.falloff_mut().bind(3);
+
+        vertex.bind();
+        index.bind_as_index();
+
+        // the surface hides the parts of the disc behind it, but the disc doesn't hide anything
+        GL!(gl::Enable(gl::BLEND));
+        GL!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+        GL!(gl::DepthMask(gl::FALSE));
+        GL!(gl::Enable(gl::POLYGON_OFFSET_FILL));
+        GL!(gl::PolygonOffset(-1., -4.));
+
+        for (i, matrix) in matrices.iter().enumerate() {
+            let mut color = base_color;
+            if i > 0 {
+                color.w *= COPY_OPACITY;
+            }
+
+            self.programm_storage.access().get::<BrushCursorProgramm>().unwrap()
+                .bind()
+                .set_uniforms(BrushCursorUniforms {
+                    view: camera.view_matrix(),
+                    projection: camera.projection_matrix(),
+                    model: *matrix,
+                    falloff_curve: 3.into(),
+                    color,
+                }).unwrap();
+
+            GL!(gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, (0) as *const c_void));
+        }
+
+        GL!(gl::Disable(gl::POLYGON_OFFSET_FILL));
+        GL!(gl::DepthMask(gl::TRUE));
+        GL!(gl::Disable(gl::BLEND));
+
+        vertex.unbind();
+        index.unbind();
+    }
+}
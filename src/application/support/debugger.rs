@@ -15,7 +15,11 @@ pub enum DebugPrimitive {
         size: Vec3,
     },
     Point(Vec3),
-    Triangle(Triangle)
+    Triangle(Triangle),
+    Line {
+        from: Vec3,
+        to: Vec3,
+    },
 }
 
 impl DebugPrimitive {
@@ -37,6 +41,7 @@ impl DebugDraw {
             DebugPrimitive::Box { corner, size } => draw_box(camera, egui_ctx, self.matrix, corner, size, self.color, self.width),
             DebugPrimitive::Point(p) => draw_point(self.matrix, self.color, p, egui_ctx, camera),
             DebugPrimitive::Triangle(t) => draw_triangle(camera, egui_ctx, self.matrix, self.color, t, self.width),
+            DebugPrimitive::Line { from, to } => draw_line(camera, egui_ctx, self.matrix, self.color, from, to, self.width),
         }
     } 
 }
@@ -109,6 +114,28 @@ fn draw_triangle(camera: &impl Camera, egui_ctx: &CtxRef, matrix: Mat4, color: C
     }
 }
 
+fn draw_line(camera: &impl Camera, egui_ctx: &CtxRef, matrix: Mat4, color: Color32, from: Vec3, to: Vec3, width: f32) {
+    let painter = egui_ctx.debug_painter();
+    let screen_size = screen_size(egui_ctx);
+
+    let m = camera.full_matrix() * matrix; 
+
+    let a = m * from.w1();
+    let b = m * to.w1();
+
+    let a = ((a.xyz() / a.w) + Vec3::ONE) * 0.5;
+    let b = ((b.xyz() / b.w) + Vec3::ONE) * 0.5;
+
+    if a.z <= -1. || a.z >= 1. || b.z <= -1. || b.z >= 1. {
+        return;
+    }
+
+    let a = Pos2::new(a.x * screen_size.x, (1. - a.y) * screen_size.y);
+    let b = Pos2::new(b.x * screen_size.x, (1. - b.y) * screen_size.y);
+
+    painter.line_segment([a, b], Stroke::new(width, color));
+}
+
 fn draw_box(
     camera: &impl Camera,
     egui_ctx: &CtxRef,
@@ -256,6 +283,16 @@ impl Debugger {
         });
     }
 
+    // drawn even when debug drawing is off, for tool feedback like the stroke stabilizer
+    pub fn draw_overlay(&mut self, primitive: DebugPrimitive, color: Color32, width: f32) {
+        self.primitives.lock().unwrap().push(DebugDraw {
+            primitive,
+            color,
+            matrix: self.matrix,
+            width
+        });
+    }
+
     pub fn perform_draw(&mut self, egui_ctx: &CtxRef, camera: &impl Camera) {
         let mut prim_lock = self.primitives.lock().unwrap();
         for d in prim_lock.iter() {
@@ -3,6 +3,9 @@ pub mod simple_quad;
 pub mod triangulation_table;
 pub mod bounds;
 pub mod brush;
+pub mod brush_cursor;
 pub mod shaders;
 pub mod debugger;
-pub mod camera_ref;
\ No newline at end of file
+pub mod camera_ref;
+pub mod symmetry;
+pub mod stroke;
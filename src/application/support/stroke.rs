@@ -0,0 +1,156 @@
+use glam::Vec3;
+
+// more dabs than this in one frame are spread out over the path instead
+const MAX_DABS_PER_FRAME: usize = 64;
+
+// Resamples the pointer path of a stroke into evenly spaced dab positions,
+// so the result doesn't depend on frame rate or on how fast the pointer moves.
+pub struct StrokePath {
+    last_point: Option<Vec3>,
+    // path length walked since the last dab
+    travelled: f32,
+    // unit direction of the last segment that had any length
+    direction: Option<Vec3>,
+}
+
+impl StrokePath {
+    pub fn new() -> Self {
+        Self { last_point: None, travelled: 0., direction: None }
+    }
+
+    // the next point starts a new segment without connecting it to the previous one
+    pub fn break_path(&mut self) {
+        self.last_point = None;
+        self.travelled = 0.;
+        self.direction = None;
+    }
+
+    pub fn direction(&self) -> Option<Vec3> {
+        self.direction
+    }
+
+    // returns the dabs placed on the path from the previous point up to the given one
+    pub fn advance(&mut self, point: Vec3, spacing: f32) -> Vec<Vec3> {
+        let Some(last_point) = self.last_point else {
+            self.last_point = Some(point);
+            self.travelled = 0.;
+            return vec![point];
+        };
+
+        let length = last_point.distance(point);
+        if length <= f32::EPSILON {
+            return Vec::new();
+        }
+        self.direction = Some((point - last_point) / length);
+        let spacing = spacing.max(length / MAX_DABS_PER_FRAME as f32).max(f32::EPSILON);
+
+        // spacing follows the brush radius, so it can shrink below the distance already walked
+        self.travelled = self.travelled.clamp(0., spacing);
+
+        let mut dabs = Vec::new();
+        let mut distance = spacing - self.travelled;
+        while distance <= length {
+            dabs.push(last_point.lerp(point, distance / length));
+            distance += spacing;
+        }
+
+        self.travelled = length - (distance - spacing);
+        self.last_point = Some(point);
+        dabs
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StabilizerMode {
+    Off,
+    // the brush is dragged behind the pointer on a string of fixed length
+    LazyRadius,
+    // the brush moves a fixed part of the way to the pointer every frame
+    Exponential,
+}
+
+impl StabilizerMode {
+    pub const ALL: [StabilizerMode; 3] = [StabilizerMode::Off, StabilizerMode::LazyRadius, StabilizerMode::Exponential];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StabilizerMode::Off => "off",
+            StabilizerMode::LazyRadius => "lazy radius",
+            StabilizerMode::Exponential => "exponential",
+        }
+    }
+}
+
+// smooths the pointer path before it is turned into dabs
+pub struct Stabilizer {
+    pub mode: StabilizerMode,
+    pub lazy_radius: f32,
+    // part of the distance to the pointer left after a 60 fps frame
+    pub smoothing: f32,
+    position: Option<Vec3>,
+}
+
+impl Stabilizer {
+    pub fn new() -> Self {
+        Self { mode: StabilizerMode::Off, lazy_radius: 0.05, smoothing: 0.8, position: None }
+    }
+
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    // moves the brush towards the pointer, frame_rate_coefficient is the frame time relative to 60 fps
+    pub fn update(&mut self, pointer: Vec3, frame_rate_coefficient: f32) -> Vec3 {
+        let position = match (self.mode, self.position) {
+            (StabilizerMode::Off, _) | (_, None) => pointer,
+            (StabilizerMode::LazyRadius, Some(position)) => {
+                let offset = position - pointer;
+                if offset.length() > self.lazy_radius {
+                    pointer + offset.normalize() * self.lazy_radius
+                }
+                else {
+                    position
+                }
+            },
+            (StabilizerMode::Exponential, Some(position)) => {
+                let remaining = self.smoothing.clamp(0., 0.99).powf(frame_rate_coefficient);
+                pointer.lerp(position, remaining)
+            },
+        };
+        self.position = Some(position);
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dabs_are_evenly_spaced_across_calls() {
+        let mut path = StrokePath::new();
+        let mut dabs = path.advance(Vec3::ZERO, 0.1);
+        for x in [0.05, 0.23, 0.24, 0.61, 1.05] {
+            dabs.extend(path.advance(Vec3::new(x, 0., 0.), 0.1));
+        }
+
+        assert_eq!(dabs.len(), 11);
+        for (i, dab) in dabs.iter().enumerate() {
+            assert!((dab.x - i as f32 * 0.1).abs() < 1e-5, "dab {i} at {dab}");
+        }
+    }
+
+    #[test]
+    fn stationary_pointer_places_no_dabs() {
+        let mut path = StrokePath::new();
+        path.advance(Vec3::ZERO, 0.1);
+        path.advance(Vec3::new(0.09, 0., 0.), 0.1);
+
+        // the radius shrinking below the walked distance must not produce nan dabs
+        assert!(path.advance(Vec3::new(0.09, 0., 0.), 0.01).is_empty());
+        let dabs = path.advance(Vec3::new(0.1, 0., 0.), 0.01);
+        assert!(!dabs.is_empty());
+        assert!(dabs.iter().all(|d| d.is_finite()));
+        assert_eq!(path.direction(), Some(Vec3::X));
+    }
+}
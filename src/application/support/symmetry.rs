@@ -0,0 +1,59 @@
+use std::f32::consts::TAU;
+
+use glam::{BVec3, Quat, Vec3};
+
+use super::brush::Brush;
+
+pub const AXIS_NAMES: [&str; 3] = ["x", "y", "z"];
+
+// world space planes the brush is mirrored across, a plane per enabled axis,
+// and copies of the brush rotated around a line parallel to one of the axes
+pub struct Symmetry {
+    pub mirror: BVec3,
+    pub plane_offset: Vec3,
+    // number of brushes spread evenly around the radial axis, 1 turns radial symmetry off
+    pub radial_count: u32,
+    pub radial_axis: usize,
+    pub radial_center: Vec3,
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self { 
+            mirror: BVec3::FALSE, 
+            plane_offset: Vec3::splat(0.5),
+            radial_count: 1,
+            radial_axis: 1,
+            radial_center: Vec3::splat(0.5),
+        }
+    }
+}
+
+impl Symmetry {
+    // the brush itself followed by its radial copies, each of them mirrored across the enabled planes
+    pub fn replicate(&self, brush: &Brush) -> Vec<Brush> {
+        let mut axis = Vec3::ZERO;
+        axis[self.radial_axis] = 1.;
+        let step = TAU / self.radial_count.max(1) as f32;
+
+        let mut brushes: Vec<_> = (0..self.radial_count.max(1))
+            .map(|i| if i == 0 { 
+                brush.clone() 
+            } else { 
+                brush.rotated(Quat::from_axis_angle(axis, step * i as f32), self.radial_center) 
+            })
+            .collect();
+
+        for axis in 0..3 {
+            if !self.mirror.test(axis) {
+                continue;
+            }
+            let mirrored: Vec<_> = brushes.iter()
+                .map(|b| b.mirrored(axis, self.plane_offset[axis]))
+                .collect();
+            brushes.extend(mirrored);
+        }
+
+        brushes
+    }
+}